@@ -0,0 +1,228 @@
+use eframe::egui;
+use image::RgbaImage;
+use libwayshot::WayshotConnection;
+use libwayshot::output::OutputInfo;
+use wayland_client::protocol::wl_output::{self, WlOutput};
+use wayland_client::{Connection, Dispatch, QueueHandle, WEnum};
+
+/// Salida de Wayland dentro de la captura, en píxeles de la imagen capturada.
+#[derive(Debug, Clone)]
+pub struct Monitor {
+    pub name: String,
+    pub rect: egui::Rect,
+    /// Posición y tamaño lógicos de la salida, comparables con los puntos de egui.
+    pub logical_rect: egui::Rect,
+    /// Factor de escala de la salida según Wayland (píxeles físicos por punto lógico).
+    pub scale: f32,
+}
+
+impl Monitor {
+    /// Píxeles de la captura por punto lógico en esta salida. libwayshot lleva cada salida a su
+    /// tamaño lógico antes de unirlas, así que en pantallas HiDPI no coincide con `scale`.
+    pub fn pixels_per_point(&self) -> egui::Vec2 {
+        self.rect.size() / self.logical_rect.size().max(egui::Vec2::splat(1.0))
+    }
+
+    /// Punto lógico del escritorio que corresponde a un píxel de la captura dentro de la salida.
+    #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+    pub fn screen_point(&self, pixel: egui::Pos2) -> egui::Pos2 {
+        self.logical_rect.min + (pixel - self.rect.min) / self.pixels_per_point()
+    }
+}
+
+pub struct Capture {
+    pub image: RgbaImage,
+    pub monitors: Vec<Monitor>,
+    /// Posición lógica de la esquina superior izquierda de la captura en el escritorio.
+    pub origin: egui::Pos2,
+    /// Píxeles de la imagen por punto lógico del escritorio.
+    pub scale: egui::Vec2,
+}
+
+/// Captura todas las salidas, o solo `monitor` si se indica.
+pub fn capture(connection: &WayshotConnection, monitor: Option<&str>) -> Result<Capture, String> {
+    let mut outputs = connection.get_all_outputs();
+    if let Some(name) = monitor {
+        outputs.retain(|output| output.name == name);
+        if outputs.is_empty() {
+            return Err(format!(
+                "No existe la salida '{}'. Salidas disponibles: {}",
+                name,
+                output_names(connection).join(", ")
+            ));
+        }
+    }
+
+    let origin = egui::pos2(
+        outputs.iter().map(|o| o.dimensions.x).min().unwrap_or(0) as f32,
+        outputs.iter().map(|o| o.dimensions.y).min().unwrap_or(0) as f32,
+    );
+    let logical_size = egui::vec2(
+        outputs
+            .iter()
+            .map(|o| o.dimensions.x + o.dimensions.width)
+            .max()
+            .unwrap_or(0) as f32
+            - origin.x,
+        outputs
+            .iter()
+            .map(|o| o.dimensions.y + o.dimensions.height)
+            .max()
+            .unwrap_or(0) as f32
+            - origin.y,
+    );
+
+    let screenshot = connection
+        .screenshot_outputs(outputs.clone(), false)
+        .map_err(|e| format!("Fallo al tomar la captura de pantalla: {}", e))?;
+    // libwayshot usa otra versión de `image`, así que se copia el búfer.
+    let (width, height) = screenshot.dimensions();
+    let image = RgbaImage::from_raw(width, height, screenshot.into_raw())
+        .ok_or("No se pudo convertir el búfer.")?;
+
+    // La imagen puede no estar en píxeles lógicos, así que se reescala cada salida.
+    let scale = egui::vec2(
+        image.width() as f32 / logical_size.x.max(1.0),
+        image.height() as f32 / logical_size.y.max(1.0),
+    );
+    let modes = output_modes(connection);
+    let monitors = outputs
+        .iter()
+        .map(|output| {
+            let logical_rect = logical_rect(output);
+            Monitor {
+                name: output.name.clone(),
+                rect: scale_rect(logical_rect.translate(-origin.to_vec2()), scale),
+                logical_rect,
+                scale: modes
+                    .iter()
+                    .find(|mode| mode.name == output.name)
+                    .map_or(1.0, |mode| mode.scale(output)),
+            }
+        })
+        .collect();
+
+    Ok(Capture {
+        image,
+        monitors,
+        origin,
+        scale,
+    })
+}
+
+pub fn output_names(connection: &WayshotConnection) -> Vec<String> {
+    connection
+        .get_all_outputs()
+        .into_iter()
+        .map(|output| output.name)
+        .collect()
+}
+
+pub fn print_outputs(connection: &WayshotConnection) {
+    for output in connection.get_all_outputs() {
+        let d = &output.dimensions;
+        println!("{}\t{}x{}+{}+{}", output.name, d.width, d.height, d.x, d.y);
+    }
+}
+
+/// Lo que anuncia cada `wl_output` y libwayshot no guarda: su escala y su modo actual.
+#[derive(Debug, Clone)]
+struct OutputMode {
+    name: String,
+    /// Escala entera del evento `scale`; con escalado fraccionario se redondea hacia arriba.
+    factor: i32,
+    /// Resolución física del modo actual.
+    size: Option<egui::Vec2>,
+}
+
+impl OutputMode {
+    /// Píxeles físicos por punto lógico. El modo da también las escalas fraccionarias; si falta,
+    /// se usa la escala entera.
+    fn scale(&self, output: &OutputInfo) -> f32 {
+        let logical = logical_rect(output).size();
+        let size = self.size.map(|size| match output.transform {
+            wl_output::Transform::_90
+            | wl_output::Transform::_270
+            | wl_output::Transform::Flipped90
+            | wl_output::Transform::Flipped270 => egui::vec2(size.y, size.x),
+            _ => size,
+        });
+        match size {
+            Some(size) if logical.x > 0.0 => size.x / logical.x,
+            _ => self.factor.max(1) as f32,
+        }
+    }
+}
+
+#[derive(Default)]
+struct OutputModes(Vec<(WlOutput, OutputMode)>);
+
+impl Dispatch<WlOutput, ()> for OutputModes {
+    fn event(
+        state: &mut Self,
+        output: &WlOutput,
+        event: wl_output::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let Some((_, mode)) = state.0.iter_mut().find(|(o, _)| o == output) else {
+            return;
+        };
+        match event {
+            wl_output::Event::Name { name } => mode.name = name,
+            wl_output::Event::Scale { factor } => mode.factor = factor,
+            wl_output::Event::Mode {
+                flags: WEnum::Value(flags),
+                width,
+                height,
+                ..
+            } if flags.contains(wl_output::Mode::Current) => {
+                mode.size = Some(egui::vec2(width as f32, height as f32));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Escala y modo de cada salida, pidiéndolos de nuevo a Wayland en una cola propia.
+fn output_modes(connection: &WayshotConnection) -> Vec<OutputMode> {
+    let mut queue = connection.conn.new_event_queue();
+    let qh = queue.handle();
+    let mut state = OutputModes::default();
+    for global in connection.globals.contents().clone_list() {
+        // El nombre de la salida llega desde la versión 4, igual que en libwayshot.
+        if global.interface == "wl_output" && global.version >= 4 {
+            let output =
+                connection
+                    .globals
+                    .registry()
+                    .bind::<WlOutput, _, _>(global.name, 4, &qh, ());
+            let mode = OutputMode {
+                name: String::new(),
+                factor: 1,
+                size: None,
+            };
+            state.0.push((output, mode));
+        }
+    }
+    if let Err(e) = queue.roundtrip(&mut state) {
+        eprintln!("[captura] No se pudo leer la escala de las salidas: {}", e);
+    }
+    state.0.into_iter().map(|(_, mode)| mode).collect()
+}
+
+fn logical_rect(output: &OutputInfo) -> egui::Rect {
+    let d = &output.dimensions;
+    egui::Rect::from_min_size(
+        egui::pos2(d.x as f32, d.y as f32),
+        egui::vec2(d.width as f32, d.height as f32),
+    )
+}
+
+fn scale_rect(rect: egui::Rect, scale: egui::Vec2) -> egui::Rect {
+    egui::Rect::from_min_max(
+        (rect.min.to_vec2() * scale).to_pos2(),
+        (rect.max.to_vec2() * scale).to_pos2(),
+    )
+}
@@ -0,0 +1,138 @@
+use clap::Parser;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "captura-extractor",
+    version,
+    about = "Captura una región de la pantalla y reconoce su texto"
+)]
+pub struct Cli {
+    /// Perfil de configuración a usar (se guarda en config.json)
+    #[arg(short = 'p', long, default_value = "default")]
+    pub profile: String,
+
+    /// Captura solo la salida indicada (p. ej. `DP-1`) y muestra la ventana en ella
+    #[arg(short = 'm', long, value_name = "NAME")]
+    pub monitor: Option<String>,
+
+    /// Lista las salidas disponibles y termina
+    #[arg(long)]
+    pub list_monitors: bool,
+
+    /// Formato del texto reconocido por Tesseract o PaddleOCR
+    #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+    #[arg(short = 't', long, value_enum)]
+    pub text_mode: Option<crate::config::TextMode>,
+
+    /// Escribe el resultado en hOCR en FILE tras cada reconocimiento
+    #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+    #[arg(long, value_name = "FILE")]
+    pub hocr: Option<std::path::PathBuf>,
+
+    /// Escribe el resultado en ALTO XML en FILE tras cada reconocimiento
+    #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+    #[arg(long, value_name = "FILE")]
+    pub alto: Option<std::path::PathBuf>,
+
+    /// Escribe el recorte como PDF buscable en FILE tras cada reconocimiento
+    #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+    #[arg(long, value_name = "FILE")]
+    pub pdf: Option<std::path::PathBuf>,
+
+    /// Escribe el resultado en JSON (con coordenadas de pantalla) en FILE tras cada reconocimiento
+    #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+    #[arg(long, value_name = "FILE")]
+    pub json: Option<std::path::PathBuf>,
+
+    /// Al soltar el ratón reconoce con el motor por defecto, copia el texto y termina
+    #[arg(short = 'a', long)]
+    pub autocopy: bool,
+
+    /// Motor por defecto para `--autocopy`
+    #[arg(short = 'e', long, value_enum)]
+    pub engine: Option<crate::config::Engine>,
+
+    /// Con `--autocopy`, escribe también el texto reconocido en stdout
+    #[arg(long)]
+    pub print: bool,
+
+    /// Al terminar cada reconocimiento muestra una notificación con el texto y acciones
+    #[arg(short = 'n', long)]
+    pub notify: bool,
+
+    /// Muestra mensajes de diagnóstico (preprocesado, tiempos, etc.)
+    #[arg(short = 'v', long)]
+    pub verbose: bool,
+
+    /// Uso interno: sirve el contenido de stdin en el portapapeles hasta que otro lo reemplace
+    #[arg(long, hide = true, value_enum)]
+    pub serve_clipboard: Option<crate::clipboard::Payload>,
+
+    /// Uso interno: portapapeles que ocupa `--serve-clipboard`
+    #[arg(long, hide = true, value_enum, default_value = "clipboard")]
+    pub selection: crate::clipboard::Selection,
+
+    /// Uso interno: notifica el texto de stdin y ejecuta la acción elegida
+    #[arg(long, hide = true)]
+    pub serve_notification: bool,
+
+    /// Idiomas de Tesseract en orden de prioridad, p. ej. `eng+spa+deu`
+    #[cfg(feature = "tesseract")]
+    #[arg(short = 'L', long)]
+    pub lang: Option<String>,
+
+    /// Preset de caracteres permitidos para Tesseract
+    #[cfg(feature = "tesseract")]
+    #[arg(long, value_enum)]
+    pub chars: Option<crate::config::CharPreset>,
+
+    /// Caracteres permitidos (tessedit_char_whitelist); implica `--chars custom`
+    #[cfg(feature = "tesseract")]
+    #[arg(long)]
+    pub whitelist: Option<String>,
+
+    /// Caracteres prohibidos (tessedit_char_blacklist); implica `--chars custom`
+    #[cfg(feature = "tesseract")]
+    #[arg(long)]
+    pub blacklist: Option<String>,
+
+    /// Archivo de patrones de usuario de Tesseract; implica `--chars custom`
+    #[cfg(feature = "tesseract")]
+    #[arg(long, value_name = "FILE")]
+    pub user_patterns: Option<std::path::PathBuf>,
+}
+
+impl Cli {
+    /// Opciones que cambian el perfil solo durante esta ejecución.
+    pub fn overrides(&self) -> crate::config::Overrides {
+        #[cfg_attr(
+            not(any(feature = "tesseract", feature = "paddleocr")),
+            allow(unused_mut)
+        )]
+        let mut overrides = crate::config::Overrides {
+            engine: self.engine,
+            autocopy: self.autocopy,
+            notify: self.notify,
+            ..Default::default()
+        };
+        #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+        {
+            overrides.text_mode = self.text_mode;
+        }
+        #[cfg(feature = "tesseract")]
+        {
+            overrides.tesseract_langs = self.lang.as_ref().map(|lang| {
+                lang.split('+')
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            });
+            overrides.tesseract_chars = self.chars;
+            overrides.tesseract_whitelist = self.whitelist.clone();
+            overrides.tesseract_blacklist = self.blacklist.clone();
+            overrides.tesseract_user_patterns = self.user_patterns.clone();
+        }
+        overrides
+    }
+}
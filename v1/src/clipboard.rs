@@ -0,0 +1,187 @@
+use arboard::{Clipboard, ImageData, LinuxClipboardKind, SetExtLinux};
+use std::borrow::Cow;
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+
+/// Primer byte que escribe `--serve-clipboard` en stdout: el contenido ya está copiado, o
+/// no se pudo copiar y le sigue el mensaje de error.
+const READY: u8 = b'0';
+const FAILED: u8 = b'1';
+
+pub enum Content {
+    Text(String),
+    /// `text/html` con una alternativa en texto plano para quien no acepte HTML.
+    #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+    Html {
+        html: String,
+        alt_text: String,
+    },
+    /// Imagen codificada en PNG; se sirve como `image/png`.
+    Png(Vec<u8>),
+}
+
+/// Tipo de contenido que recibe por stdin el proceso lanzado con `--serve-clipboard`.
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Payload {
+    Text,
+    Html,
+    Png,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Selection {
+    Clipboard,
+    /// Selección primaria (pegar con el botón central).
+    Primary,
+}
+
+impl Payload {
+    fn arg(&self) -> &'static str {
+        match self {
+            Payload::Text => "text",
+            Payload::Html => "html",
+            Payload::Png => "png",
+        }
+    }
+}
+
+impl Selection {
+    fn arg(&self) -> &'static str {
+        match self {
+            Selection::Clipboard => "clipboard",
+            Selection::Primary => "primary",
+        }
+    }
+}
+
+/// Copia `content` desde un proceso hijo que sigue sirviendo el portapapeles después de cerrar
+/// la ventana, hasta que otra aplicación lo reemplace (como hace `wl-copy`). Vuelve cuando el
+/// hijo confirma que el contenido ya está en el portapapeles o con el error que haya dado.
+pub fn copy(content: Content, selection: Selection) -> io::Result<()> {
+    let (payload, data) = match content {
+        Content::Text(text) => (Payload::Text, text.into_bytes()),
+        #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+        Content::Html { html, alt_text } => (
+            Payload::Html,
+            format!("{}\0{}", html, alt_text).into_bytes(),
+        ),
+        Content::Png(png) => (Payload::Png, png),
+    };
+
+    let mut child = Command::new(std::env::current_exe()?)
+        .args([
+            "--serve-clipboard",
+            payload.arg(),
+            "--selection",
+            selection.arg(),
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    // Al soltar stdin el hijo ve el final de los datos.
+    let written = child
+        .stdin
+        .take()
+        .map_or(Ok(()), |mut stdin| stdin.write_all(&data));
+
+    let mut status = [0u8];
+    let mut stdout = child.stdout.take().expect("stdout del hijo");
+    let result = match written.and_then(|()| stdout.read(&mut status)) {
+        Ok(1) if status[0] == READY => Ok(()),
+        Ok(1) if status[0] == FAILED => {
+            let mut message = String::new();
+            let _ = stdout.read_to_string(&mut message);
+            Err(io::Error::other(message.trim().to_string()))
+        }
+        Ok(_) => Err(io::Error::other(
+            "el proceso del portapapeles terminó sin copiar",
+        )),
+        Err(e) => Err(e),
+    };
+    // El hijo vive hasta que otra aplicación ocupe el portapapeles; se espera en otro hilo
+    // para que no quede como proceso zombi.
+    std::thread::spawn(move || child.wait());
+    result
+}
+
+/// Contenido ya decodificado, listo para ofrecerlo en el portapapeles.
+enum Offer {
+    Text(String),
+    Html { html: String, alt_text: String },
+    Image(ImageData<'static>),
+}
+
+impl Offer {
+    fn read(payload: Payload) -> Result<Offer, String> {
+        let mut data = Vec::new();
+        io::stdin()
+            .read_to_end(&mut data)
+            .map_err(|e| format!("No se pudo leer el contenido a copiar: {}", e))?;
+        Ok(match payload {
+            Payload::Text => Offer::Text(String::from_utf8_lossy(&data).into_owned()),
+            Payload::Html => {
+                let data = String::from_utf8_lossy(&data);
+                let (html, alt_text) = data.split_once('\0').unwrap_or((&data, ""));
+                Offer::Html {
+                    html: html.to_string(),
+                    alt_text: alt_text.to_string(),
+                }
+            }
+            Payload::Png => {
+                let image = image::load_from_memory(&data)
+                    .map_err(|e| format!("No se pudo decodificar la imagen: {}", e))?
+                    .to_rgba8();
+                Offer::Image(ImageData {
+                    width: image.width() as usize,
+                    height: image.height() as usize,
+                    bytes: Cow::Owned(image.into_raw()),
+                })
+            }
+        })
+    }
+
+    /// Con `wait`, no vuelve hasta que otra aplicación ocupa el portapapeles.
+    fn set(
+        &self,
+        clipboard: &mut Clipboard,
+        kind: LinuxClipboardKind,
+        wait: bool,
+    ) -> Result<(), String> {
+        let mut set = clipboard.set().clipboard(kind);
+        if wait {
+            set = set.wait();
+        }
+        match self {
+            Offer::Text(text) => set.text(text.as_str()),
+            Offer::Html { html, alt_text } => set.html(html.as_str(), Some(alt_text.as_str())),
+            Offer::Image(image) => set.image(image.clone()),
+        }
+        .map_err(|e| format!("Error al copiar al portapapeles: {}", e))
+    }
+}
+
+/// Punto de entrada de `--serve-clipboard`: lee el contenido de stdin, lo copia, informa a
+/// `copy` por stdout y lo sigue sirviendo hasta que otra aplicación ocupe el portapapeles.
+pub fn serve(payload: Payload, selection: Selection) -> Result<(), String> {
+    let kind = match selection {
+        Selection::Clipboard => LinuxClipboardKind::Clipboard,
+        Selection::Primary => LinuxClipboardKind::Primary,
+    };
+    let copied = Offer::read(payload).and_then(|offer| {
+        let mut clipboard =
+            Clipboard::new().map_err(|e| format!("No se pudo abrir el portapapeles: {}", e))?;
+        offer.set(&mut clipboard, kind, false)?;
+        Ok((clipboard, offer))
+    });
+
+    let mut stdout = io::stdout();
+    let status = match &copied {
+        Ok(_) => vec![READY],
+        Err(e) => [&[FAILED], e.as_bytes()].concat(),
+    };
+    let _ = stdout.write_all(&status).and_then(|()| stdout.flush());
+    let (mut clipboard, offer) = copied?;
+
+    // La primera copia no espera; esta vuelve a ofrecer lo mismo y espera a que lo reemplacen.
+    offer.set(&mut clipboard, kind, true)
+}
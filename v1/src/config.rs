@@ -0,0 +1,461 @@
+use crate::preprocess::Preprocess;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const APP_DIR: &str = "captura-extractor";
+const CONFIG_FILE: &str = "config.json";
+const DEFAULT_FILE_TEMPLATE: &str = "captura-{timestamp}";
+const DEFAULT_TRANSLATE_URL: &str =
+    "https://translate.google.com/?sl=auto&tl=es&op=translate&text={text}";
+
+/// Restricción de caracteres para Tesseract (`tessedit_char_whitelist`/`blacklist`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum CharPreset {
+    #[default]
+    None,
+    Digits,
+    Hex,
+    Alphanumeric,
+    Custom,
+}
+
+#[cfg(feature = "tesseract")]
+impl CharPreset {
+    pub const ALL: [CharPreset; 5] = [
+        CharPreset::None,
+        CharPreset::Digits,
+        CharPreset::Hex,
+        CharPreset::Alphanumeric,
+        CharPreset::Custom,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CharPreset::None => "None",
+            CharPreset::Digits => "Digits",
+            CharPreset::Hex => "Hex",
+            CharPreset::Alphanumeric => "Alphanumeric",
+            CharPreset::Custom => "Custom",
+        }
+    }
+
+    /// Lista blanca fija de los presets predefinidos; `Custom` usa la del perfil.
+    pub fn whitelist(&self) -> Option<&'static str> {
+        match self {
+            CharPreset::Digits => Some("0123456789"),
+            CharPreset::Hex => Some("0123456789abcdefABCDEF"),
+            CharPreset::Alphanumeric => {
+                Some("0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz")
+            }
+            CharPreset::None | CharPreset::Custom => None,
+        }
+    }
+}
+
+/// Cómo se convierte el resultado de Tesseract o PaddleOCR en texto.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum TextMode {
+    /// Todo en una línea.
+    SingleLine,
+    /// Una línea de texto por cada línea reconocida.
+    Lines,
+    /// Une las líneas de cada párrafo y separa los párrafos con una línea en blanco.
+    #[default]
+    Paragraphs,
+    /// Conserva la disposición: sangrías y columnas a partir de la posición de las cajas.
+    Layout,
+}
+
+#[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+impl TextMode {
+    pub const ALL: [TextMode; 4] = [
+        TextMode::SingleLine,
+        TextMode::Lines,
+        TextMode::Paragraphs,
+        TextMode::Layout,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TextMode::SingleLine => "Single line",
+            TextMode::Lines => "Lines",
+            TextMode::Paragraphs => "Paragraphs",
+            TextMode::Layout => "Layout",
+        }
+    }
+}
+
+/// Motor que se usa sin pasar por el menú, p. ej. con `--autocopy`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Engine {
+    /// El primero compilado de: Tesseract, PaddleOCR, Ollama, Gemini.
+    #[default]
+    Auto,
+    Tesseract,
+    Paddleocr,
+    Ollama,
+    Gemini,
+}
+
+impl Engine {
+    pub fn is_available(self) -> bool {
+        match self {
+            Engine::Auto => true,
+            Engine::Tesseract => cfg!(feature = "tesseract"),
+            Engine::Paddleocr => cfg!(feature = "paddleocr"),
+            Engine::Ollama => cfg!(feature = "ollama"),
+            Engine::Gemini => cfg!(feature = "gemini"),
+        }
+    }
+
+    /// El motor indicado si está compilado o, si no, el primero disponible.
+    pub fn resolve(self) -> Option<Engine> {
+        if self != Engine::Auto && self.is_available() {
+            return Some(self);
+        }
+        [
+            Engine::Tesseract,
+            Engine::Paddleocr,
+            Engine::Ollama,
+            Engine::Gemini,
+        ]
+        .into_iter()
+        .find(|engine| engine.is_available())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    /// Idiomas de Tesseract en orden de prioridad; se pasan como `eng+spa+deu`.
+    pub tesseract_langs: Vec<String>,
+    pub tesseract_chars: CharPreset,
+    /// Solo se aplican con el preset `custom`.
+    pub tesseract_whitelist: String,
+    pub tesseract_blacklist: String,
+    pub tesseract_user_patterns: Option<PathBuf>,
+    pub preprocess: Preprocess,
+    pub text_mode: TextMode,
+    /// Las palabras con una confianza menor (0–100) se descartan del resultado.
+    pub min_confidence: f32,
+    pub engine: Engine,
+    /// Al soltar el ratón reconoce con `engine`, copia el texto y cierra la ventana.
+    pub autocopy: bool,
+    /// Muestra una notificación con el texto al terminar cada reconocimiento.
+    pub notify: bool,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            tesseract_langs: Vec::new(),
+            tesseract_chars: CharPreset::default(),
+            tesseract_whitelist: String::new(),
+            tesseract_blacklist: String::new(),
+            tesseract_user_patterns: None,
+            preprocess: Preprocess::default(),
+            text_mode: TextMode::default(),
+            min_confidence: 10.0,
+            engine: Engine::default(),
+            autocopy: false,
+            notify: false,
+        }
+    }
+}
+
+/// Cambios al perfil pedidos en la línea de órdenes. Solo valen para la sesión: nunca se guardan
+/// en `config.json`.
+#[derive(Debug, Clone, Default)]
+pub struct Overrides {
+    pub engine: Option<Engine>,
+    pub autocopy: bool,
+    pub notify: bool,
+    pub text_mode: Option<TextMode>,
+    pub tesseract_langs: Option<Vec<String>>,
+    pub tesseract_chars: Option<CharPreset>,
+    pub tesseract_whitelist: Option<String>,
+    pub tesseract_blacklist: Option<String>,
+    pub tesseract_user_patterns: Option<PathBuf>,
+}
+
+impl Overrides {
+    pub fn apply(&self, profile: &mut Profile) {
+        profile.autocopy |= self.autocopy;
+        profile.notify |= self.notify;
+        if let Some(engine) = self.engine {
+            profile.engine = engine;
+        }
+        if let Some(mode) = self.text_mode {
+            profile.text_mode = mode;
+        }
+        if let Some(langs) = &self.tesseract_langs {
+            profile.tesseract_langs = langs.clone();
+        }
+        // Las listas y los patrones implican el preset `custom`, salvo que se pida otro.
+        if let Some(whitelist) = &self.tesseract_whitelist {
+            profile.tesseract_whitelist = whitelist.clone();
+            profile.tesseract_chars = CharPreset::Custom;
+        }
+        if let Some(blacklist) = &self.tesseract_blacklist {
+            profile.tesseract_blacklist = blacklist.clone();
+            profile.tesseract_chars = CharPreset::Custom;
+        }
+        if let Some(patterns) = &self.tesseract_user_patterns {
+            profile.tesseract_user_patterns = Some(patterns.clone());
+            profile.tesseract_chars = CharPreset::Custom;
+        }
+        if let Some(preset) = self.tesseract_chars {
+            profile.tesseract_chars = preset;
+        }
+    }
+
+    /// Perfil que se guarda a partir del de la sesión: los campos que conservan el valor impuesto
+    /// por la línea de órdenes vuelven al de `saved`; los que se cambiaron en la interfaz se guardan.
+    pub fn persistent(&self, session: &Profile, saved: &Profile) -> Profile {
+        let mut applied = saved.clone();
+        self.apply(&mut applied);
+        let mut profile = session.clone();
+        let chars = self.tesseract_chars.is_some()
+            || self.tesseract_whitelist.is_some()
+            || self.tesseract_blacklist.is_some()
+            || self.tesseract_user_patterns.is_some();
+
+        // Devuelve el campo a su valor guardado si sigue siendo el que impuso la línea de órdenes.
+        macro_rules! restore {
+            ($overridden:expr, $field:ident) => {
+                if $overridden && profile.$field == applied.$field {
+                    profile.$field = saved.$field.clone();
+                }
+            };
+        }
+        restore!(self.autocopy, autocopy);
+        restore!(self.notify, notify);
+        restore!(self.engine.is_some(), engine);
+        restore!(self.text_mode.is_some(), text_mode);
+        restore!(self.tesseract_langs.is_some(), tesseract_langs);
+        restore!(chars, tesseract_chars);
+        restore!(self.tesseract_whitelist.is_some(), tesseract_whitelist);
+        restore!(self.tesseract_blacklist.is_some(), tesseract_blacklist);
+        restore!(
+            self.tesseract_user_patterns.is_some(),
+            tesseract_user_patterns
+        );
+        profile
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Directorio con `det.onnx`, `rec.onnx` y `dict.txt` para el motor PaddleOCR en ONNX.
+    pub paddle_model_dir: Option<PathBuf>,
+    /// Carpeta donde se guardan las exportaciones y las imágenes del menú; por defecto, `$HOME`.
+    pub export_dir: Option<PathBuf>,
+    /// Nombre (sin extensión) de los archivos guardados desde el menú. Admite `{timestamp}`,
+    /// `{profile}`, `{width}` y `{height}`; vacío equivale a `captura-{timestamp}`.
+    pub file_template: String,
+    /// URL que abre la acción "Translate" de la notificación; `{text}` se sustituye por el
+    /// texto codificado. Vacío equivale a Google Translate al español.
+    pub translate_url: String,
+    pub profiles: BTreeMap<String, Profile>,
+    /// `config.json` existe pero no se pudo interpretar: no se sobrescribe para no perder los perfiles.
+    #[serde(skip)]
+    unreadable: bool,
+}
+
+impl Config {
+    pub fn load() -> Self {
+        config_path().map_or_else(Self::default, |path| Self::load_from(&path))
+    }
+
+    fn load_from(path: &Path) -> Self {
+        match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                eprintln!(
+                    "[config] Error al leer '{}': {}. Se usan los valores por defecto y no se \
+                     guardarán cambios hasta corregirlo.",
+                    path.display(),
+                    e
+                );
+                Self {
+                    unreadable: true,
+                    ..Self::default()
+                }
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = config_path().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "No se pudo determinar el directorio de configuración",
+            )
+        })?;
+        self.save_to(&path)
+    }
+
+    fn save_to(&self, path: &Path) -> io::Result<()> {
+        if self.unreadable {
+            return Err(io::Error::other(format!(
+                "'{}' no se pudo leer al iniciar; no se sobrescribe",
+                path.display()
+            )));
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let contents = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, contents)
+    }
+
+    pub fn profile(&self, name: &str) -> Profile {
+        self.profiles.get(name).cloned().unwrap_or_default()
+    }
+
+    #[cfg(feature = "paddle_onnx")]
+    pub fn paddle_model_dir(&self) -> PathBuf {
+        self.paddle_model_dir
+            .clone()
+            .or_else(|| data_dir().map(|dir| dir.join("paddle")))
+            .unwrap_or_else(|| PathBuf::from("paddle"))
+    }
+
+    pub fn export_dir(&self) -> PathBuf {
+        self.export_dir
+            .clone()
+            .or_else(|| std::env::var_os("HOME").map(PathBuf::from))
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+
+    /// Ruta de un archivo nuevo según `file_template`; si el resultado es relativo, se guarda
+    /// en `export_dir`.
+    pub fn output_path(&self, extension: &str, values: &[(&str, String)]) -> PathBuf {
+        let template = if self.file_template.is_empty() {
+            DEFAULT_FILE_TEMPLATE
+        } else {
+            &self.file_template
+        };
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let mut name = template.replace("{timestamp}", &timestamp.to_string());
+        for (key, value) in values {
+            name = name.replace(&format!("{{{}}}", key), value);
+        }
+        let path = PathBuf::from(format!("{}.{}", name, extension));
+        if path.is_absolute() {
+            path
+        } else {
+            self.export_dir().join(path)
+        }
+    }
+
+    pub fn translate_url(&self, text: &str) -> String {
+        let template = if self.translate_url.is_empty() {
+            DEFAULT_TRANSLATE_URL
+        } else {
+            &self.translate_url
+        };
+        template.replace("{text}", &percent_encode(text))
+    }
+}
+
+/// Codifica `text` para usarlo como valor en la query de una URL.
+fn percent_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn xdg_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+    std::env::var_os(var)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(fallback)))
+        .map(|dir| dir.join(APP_DIR))
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
+
+fn config_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(CONFIG_FILE))
+}
+
+#[cfg(feature = "paddle_onnx")]
+pub fn data_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_DATA_HOME", ".local/share")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn langs(langs: &[&str]) -> Vec<String> {
+        langs.iter().map(|l| l.to_string()).collect()
+    }
+
+    fn overrides() -> Overrides {
+        Overrides {
+            tesseract_langs: Some(langs(&["spa"])),
+            ..Overrides::default()
+        }
+    }
+
+    #[test]
+    fn ui_changes_are_saved_over_overrides() {
+        let saved = Profile {
+            tesseract_langs: langs(&["eng"]),
+            ..Profile::default()
+        };
+        let mut session = saved.clone();
+        overrides().apply(&mut session);
+        assert_eq!(session.tesseract_langs, langs(&["spa"]));
+
+        session.tesseract_langs = langs(&["deu", "spa"]);
+        let persistent = overrides().persistent(&session, &saved);
+        assert_eq!(persistent.tesseract_langs, langs(&["deu", "spa"]));
+    }
+
+    #[test]
+    fn untouched_overrides_are_not_saved() {
+        let saved = Profile {
+            tesseract_langs: langs(&["eng"]),
+            ..Profile::default()
+        };
+        let mut session = saved.clone();
+        overrides().apply(&mut session);
+        let persistent = overrides().persistent(&session, &saved);
+        assert_eq!(persistent.tesseract_langs, langs(&["eng"]));
+    }
+
+    #[test]
+    fn unreadable_config_is_not_overwritten() {
+        let dir = std::env::temp_dir().join(format!("{}-test-{}", APP_DIR, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(CONFIG_FILE);
+        fs::write(&path, "{ \"profiles\": ").unwrap();
+
+        let config = Config::load_from(&path);
+        assert!(config.save_to(&path).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{ \"profiles\": ");
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,453 @@
+use crate::{OcrLine, pdf};
+use eframe::egui;
+use image::DynamicImage;
+use serde::Serialize;
+use std::fmt::Write;
+
+const SOFTWARE: &str = env!("CARGO_PKG_NAME");
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Versión del esquema JSON; cambia solo si se quitan o renombran campos.
+pub const JSON_VERSION: u32 = 1;
+
+/// Datos del reconocimiento que no están en las líneas: motor, modelo y tiempos.
+#[derive(Debug, Clone, Default)]
+pub struct Metadata {
+    pub engine: &'static str,
+    pub model: String,
+    pub language: Option<String>,
+    pub preprocess_ms: u64,
+    pub recognition_ms: u64,
+}
+
+/// Resultado a exportar. Las cajas de las líneas están en píxeles del recorte.
+pub struct Document<'a> {
+    pub lines: &'a [OcrLine],
+    pub image: &'a DynamicImage,
+    pub text: &'a str,
+    pub metadata: &'a Metadata,
+    /// Posición lógica en el escritorio del píxel (0, 0) del recorte.
+    pub screen_origin: egui::Pos2,
+    /// Píxeles del recorte por punto lógico del escritorio.
+    pub screen_scale: egui::Vec2,
+}
+
+impl Document<'_> {
+    fn size(&self) -> egui::Vec2 {
+        egui::vec2(self.image.width() as f32, self.image.height() as f32)
+    }
+
+    fn to_screen(&self, p: egui::Pos2) -> egui::Pos2 {
+        self.screen_origin + p.to_vec2() / self.screen_scale
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Hocr,
+    Alto,
+    /// PDF buscable: el recorte con una capa de texto invisible.
+    Pdf,
+    Json,
+}
+
+impl Format {
+    pub const ALL: [Format; 4] = [Format::Hocr, Format::Alto, Format::Pdf, Format::Json];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Format::Hocr => "hOCR",
+            Format::Alto => "ALTO XML",
+            Format::Pdf => "Searchable PDF",
+            Format::Json => "JSON",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Hocr => "hocr",
+            Format::Alto => "xml",
+            Format::Pdf => "pdf",
+            Format::Json => "json",
+        }
+    }
+
+    pub fn render(&self, document: &Document) -> Vec<u8> {
+        match self {
+            Format::Hocr => hocr(document.lines, document.size()).into_bytes(),
+            Format::Alto => alto(document.lines, document.size()).into_bytes(),
+            Format::Pdf => pdf::render(document.image, document.lines),
+            Format::Json => json(document).into_bytes(),
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Líneas consecutivas del mismo párrafo.
+fn paragraphs(lines: &[OcrLine]) -> impl Iterator<Item = &[OcrLine]> {
+    lines.chunk_by(|a, b| a.paragraph == b.paragraph)
+}
+
+fn union(lines: &[OcrLine]) -> egui::Rect {
+    lines
+        .iter()
+        .fold(egui::Rect::NOTHING, |acc, l| acc.union(l.bbox))
+}
+
+fn hocr_bbox(rect: egui::Rect) -> String {
+    format!(
+        "bbox {} {} {} {}",
+        rect.min.x.round(),
+        rect.min.y.round(),
+        rect.max.x.round(),
+        rect.max.y.round()
+    )
+}
+
+fn hocr(lines: &[OcrLine], size: egui::Vec2) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(
+        "<!DOCTYPE html PUBLIC \"-//W3C//DTD XHTML 1.0 Transitional//EN\" \
+         \"http://www.w3.org/TR/xhtml1/DTD/xhtml1-transitional.dtd\">\n",
+    );
+    out.push_str("<html xmlns=\"http://www.w3.org/1999/xhtml\">\n <head>\n  <title></title>\n");
+    out.push_str("  <meta http-equiv=\"Content-Type\" content=\"text/html;charset=utf-8\"/>\n");
+    let _ = writeln!(
+        out,
+        "  <meta name=\"ocr-system\" content=\"{} {}\"/>",
+        SOFTWARE, VERSION
+    );
+    out.push_str(
+        "  <meta name=\"ocr-capabilities\" content=\"ocr_page ocr_par ocr_line ocrx_word ocrp_wconf ocrp_poly\"/>\n",
+    );
+    out.push_str(" </head>\n <body>\n");
+    let _ = writeln!(
+        out,
+        "  <div class=\"ocr_page\" id=\"page_1\" title=\"{}\">",
+        hocr_bbox(egui::Rect::from_min_size(egui::Pos2::ZERO, size))
+    );
+
+    let (mut line_id, mut word_id) = (0, 0);
+    for (par_id, paragraph) in paragraphs(lines).enumerate() {
+        let _ = writeln!(
+            out,
+            "   <p class=\"ocr_par\" id=\"par_1_{}\" title=\"{}\">",
+            par_id + 1,
+            hocr_bbox(union(paragraph))
+        );
+        for line in paragraph {
+            line_id += 1;
+            let _ = writeln!(
+                out,
+                "    <span class=\"ocr_line\" id=\"line_1_{}\" title=\"{}\">",
+                line_id,
+                hocr_bbox(line.bbox)
+            );
+            for word in &line.words {
+                word_id += 1;
+                let mut title = hocr_bbox(word.bbox);
+                if let Some(confidence) = word.confidence {
+                    let _ = write!(title, "; x_wconf {:.0}", confidence.clamp(0.0, 100.0));
+                }
+                if let Some(polygon) = word.polygon {
+                    title.push_str("; poly");
+                    for p in polygon {
+                        let _ = write!(title, " {} {}", p.x.round(), p.y.round());
+                    }
+                }
+                let _ = writeln!(
+                    out,
+                    "     <span class=\"ocrx_word\" id=\"word_1_{}\" title=\"{}\">{}</span>",
+                    word_id,
+                    title,
+                    escape(word.text.trim())
+                );
+            }
+            out.push_str("    </span>\n");
+        }
+        out.push_str("   </p>\n");
+    }
+
+    out.push_str("  </div>\n </body>\n</html>\n");
+    out
+}
+
+fn alto_position(rect: egui::Rect) -> String {
+    format!(
+        "HPOS=\"{}\" VPOS=\"{}\" WIDTH=\"{}\" HEIGHT=\"{}\"",
+        rect.min.x.round(),
+        rect.min.y.round(),
+        rect.width().round(),
+        rect.height().round()
+    )
+}
+
+fn alto(lines: &[OcrLine], size: egui::Vec2) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(
+        "<alto xmlns=\"http://www.loc.gov/standards/alto/ns-v4#\" \
+         xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" \
+         xsi:schemaLocation=\"http://www.loc.gov/standards/alto/ns-v4# \
+         http://www.loc.gov/alto/v4/alto-4-2.xsd\">\n",
+    );
+    out.push_str("  <Description>\n    <MeasurementUnit>pixel</MeasurementUnit>\n");
+    let _ = writeln!(
+        out,
+        "    <OCRProcessing ID=\"OCR_0\">\n      <ocrProcessingStep>\n        <processingSoftware>\n          \
+         <softwareName>{}</softwareName>\n          <softwareVersion>{}</softwareVersion>\n        \
+         </processingSoftware>\n      </ocrProcessingStep>\n    </OCRProcessing>",
+        SOFTWARE, VERSION
+    );
+    out.push_str("  </Description>\n  <Layout>\n");
+    let page = egui::Rect::from_min_size(egui::Pos2::ZERO, size);
+    let _ = writeln!(
+        out,
+        "    <Page ID=\"page_1\" PHYSICAL_IMG_NR=\"1\" WIDTH=\"{}\" HEIGHT=\"{}\">",
+        size.x.round(),
+        size.y.round()
+    );
+    let _ = writeln!(out, "      <PrintSpace {}>", alto_position(page));
+
+    let (mut line_id, mut word_id) = (0, 0);
+    for (block_id, paragraph) in paragraphs(lines).enumerate() {
+        let _ = writeln!(
+            out,
+            "        <TextBlock ID=\"block_{}\" {}>",
+            block_id + 1,
+            alto_position(union(paragraph))
+        );
+        for line in paragraph {
+            line_id += 1;
+            let _ = writeln!(
+                out,
+                "          <TextLine ID=\"line_{}\" {}>",
+                line_id,
+                alto_position(line.bbox)
+            );
+            for (i, word) in line.words.iter().enumerate() {
+                if i > 0 {
+                    let previous = line.words[i - 1].bbox;
+                    let _ = writeln!(
+                        out,
+                        "            <SP HPOS=\"{}\" VPOS=\"{}\" WIDTH=\"{}\"/>",
+                        previous.max.x.round(),
+                        previous.min.y.round(),
+                        (word.bbox.min.x - previous.max.x).max(0.0).round()
+                    );
+                }
+                word_id += 1;
+                let mut attributes = format!(
+                    "ID=\"string_{}\" CONTENT=\"{}\" {}",
+                    word_id,
+                    escape(word.text.trim()),
+                    alto_position(word.bbox)
+                );
+                if let Some(confidence) = word.confidence {
+                    let _ = write!(
+                        attributes,
+                        " WC=\"{:.2}\"",
+                        (confidence / 100.0).clamp(0.0, 1.0)
+                    );
+                }
+                match word.polygon {
+                    Some(polygon) => {
+                        let points: Vec<String> = polygon
+                            .iter()
+                            .map(|p| format!("{},{}", p.x.round(), p.y.round()))
+                            .collect();
+                        let _ = writeln!(
+                            out,
+                            "            <String {}>\n              <Shape><Polygon POINTS=\"{}\"/></Shape>\n            </String>",
+                            attributes,
+                            points.join(" ")
+                        );
+                    }
+                    None => {
+                        let _ = writeln!(out, "            <String {}/>", attributes);
+                    }
+                }
+            }
+            out.push_str("          </TextLine>\n");
+        }
+        out.push_str("        </TextBlock>\n");
+    }
+
+    out.push_str("      </PrintSpace>\n    </Page>\n  </Layout>\n</alto>\n");
+    out
+}
+
+#[derive(Serialize)]
+struct JsonRect {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+impl JsonRect {
+    fn new(rect: egui::Rect) -> Self {
+        Self {
+            x: rect.min.x,
+            y: rect.min.y,
+            width: rect.width(),
+            height: rect.height(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonTimings {
+    preprocess_ms: u64,
+    recognition_ms: u64,
+}
+
+#[derive(Serialize)]
+struct JsonSelection {
+    /// Tamaño del recorte en píxeles.
+    width: u32,
+    height: u32,
+    screen_bbox: JsonRect,
+}
+
+#[derive(Serialize)]
+struct JsonWord<'a> {
+    text: &'a str,
+    confidence: Option<f32>,
+    bbox: JsonRect,
+    screen_bbox: JsonRect,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    polygon: Option<Vec<[f32; 2]>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    screen_polygon: Option<Vec<[f32; 2]>>,
+}
+
+#[derive(Serialize)]
+struct JsonLine<'a> {
+    paragraph: usize,
+    bbox: JsonRect,
+    screen_bbox: JsonRect,
+    words: Vec<JsonWord<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonResult<'a> {
+    version: u32,
+    software: String,
+    engine: &'a str,
+    model: &'a str,
+    language: Option<&'a str>,
+    timings: JsonTimings,
+    selection: JsonSelection,
+    text: &'a str,
+    lines: Vec<JsonLine<'a>>,
+}
+
+fn json(document: &Document) -> String {
+    let screen_rect = |rect: egui::Rect| {
+        JsonRect::new(egui::Rect::from_min_max(
+            document.to_screen(rect.min),
+            document.to_screen(rect.max),
+        ))
+    };
+    let metadata = document.metadata;
+    let result = JsonResult {
+        version: JSON_VERSION,
+        software: format!("{} {}", SOFTWARE, VERSION),
+        engine: metadata.engine,
+        model: &metadata.model,
+        language: metadata.language.as_deref(),
+        timings: JsonTimings {
+            preprocess_ms: metadata.preprocess_ms,
+            recognition_ms: metadata.recognition_ms,
+        },
+        selection: JsonSelection {
+            width: document.image.width(),
+            height: document.image.height(),
+            screen_bbox: screen_rect(egui::Rect::from_min_size(egui::Pos2::ZERO, document.size())),
+        },
+        text: document.text,
+        lines: document
+            .lines
+            .iter()
+            .map(|line| JsonLine {
+                paragraph: line.paragraph,
+                bbox: JsonRect::new(line.bbox),
+                screen_bbox: screen_rect(line.bbox),
+                words: line
+                    .words
+                    .iter()
+                    .map(|word| JsonWord {
+                        text: word.text.trim(),
+                        confidence: word.confidence,
+                        bbox: JsonRect::new(word.bbox),
+                        screen_bbox: screen_rect(word.bbox),
+                        polygon: word.polygon.map(|q| q.iter().map(|p| [p.x, p.y]).collect()),
+                        screen_polygon: word.polygon.map(|q| {
+                            q.iter()
+                                .map(|&p| {
+                                    let p = document.to_screen(p);
+                                    [p.x, p.y]
+                                })
+                                .collect()
+                        }),
+                    })
+                    .collect(),
+            })
+            .collect(),
+    };
+    serde_json::to_string_pretty(&result).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OcrWord;
+
+    fn lines() -> Vec<OcrLine> {
+        let words = vec![
+            OcrWord::test("<a&b>", 0.0, 0.0, 40.0, 10.0),
+            OcrWord::test("\"it's\"", 50.0, 0.0, 40.0, 10.0),
+        ];
+        vec![OcrLine::test(words, 0)]
+    }
+
+    #[test]
+    fn escapes_markup() {
+        assert_eq!(
+            escape(r#"<a href="x">Tom & Jerry's</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&apos;s&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn hocr_escapes_word_text() {
+        let hocr = hocr(&lines(), egui::vec2(100.0, 20.0));
+        assert!(hocr.contains(">&lt;a&amp;b&gt;</span>"));
+        assert!(hocr.contains(">&quot;it&apos;s&quot;</span>"));
+        assert!(hocr.contains("title=\"bbox 0 0 40 10; x_wconf 90\""));
+        assert!(!hocr.contains("<a&b>"));
+    }
+
+    #[test]
+    fn alto_escapes_content_attribute() {
+        let alto = alto(&lines(), egui::vec2(100.0, 20.0));
+        assert!(alto.contains("CONTENT=\"&lt;a&amp;b&gt;\""));
+        assert!(alto.contains("CONTENT=\"&quot;it&apos;s&quot;\""));
+        assert!(alto.contains("<SP HPOS=\"40\" VPOS=\"0\" WIDTH=\"10\"/>"));
+    }
+}
@@ -73,7 +73,7 @@ impl GeminiClient {
         }
     }
 
-    pub async fn generate(&self, image_bytes: Vec<u8>, sender: Sender<String>) {
+    pub async fn generate(&self, image_bytes: Vec<u8>, sender: Sender<Result<String, String>>) {
         let base64_image = STANDARD.encode(&image_bytes);
 
         let request_body = GeminiRequest {
@@ -114,18 +114,18 @@ impl GeminiClient {
                         Ok(gemini_response) => {
                             let text_result = gemini_response
                                 .candidates
-                                .get(0)
-                                .and_then(|c| c.content.parts.get(0))
+                                .first()
+                                .and_then(|c| c.content.parts.first())
                                 .map_or("".to_string(), |p| p.text.clone());
 
-                            if sender.send(text_result).is_err() {
+                            if sender.send(Ok(text_result)).is_err() {
                                 eprintln!("[gemini.rs] El receptor del canal se cerró.");
                             }
                         }
                         Err(e) => {
                             let error_msg = format!("[gemini.rs] Error al decodificar JSON: {}", e);
                             eprintln!("{}", error_msg);
-                            let _ = sender.send(error_msg);
+                            let _ = sender.send(Err(error_msg));
                         }
                     }
                 } else {
@@ -137,13 +137,13 @@ impl GeminiClient {
                     let error_msg =
                         format!("[gemini.rs] Error de API: {} - {}", status, error_body);
                     eprintln!("{}", error_msg);
-                    let _ = sender.send(error_msg);
+                    let _ = sender.send(Err(error_msg));
                 }
             }
             Err(e) => {
                 let error_msg = format!("[gemini.rs] Error de red: {}", e);
                 eprintln!("{}", error_msg);
-                let _ = sender.send(error_msg);
+                let _ = sender.send(Err(error_msg));
             }
         }
     }
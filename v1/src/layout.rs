@@ -0,0 +1,476 @@
+use crate::config::TextMode;
+use crate::{OcrLine, OcrWord};
+use eframe::egui;
+use std::ops::Range;
+
+/// Hueco mínimo entre columnas (y entre trozos de una misma línea), en alturas de línea.
+const COLUMN_GAP: f32 = 1.2;
+/// Hueco vertical, en alturas de línea, a partir del cual empieza un párrafo nuevo.
+const PARAGRAPH_GAP: f32 = 0.8;
+/// Diferencia máxima entre los centros de dos segmentos de la misma fila, en alturas de línea.
+const ROW_TOLERANCE: f32 = 0.5;
+/// Máximo de líneas en blanco seguidas en el modo `Layout`.
+const MAX_BLANK_LINES: usize = 2;
+
+/// Reordena las líneas en orden natural de lectura: separa columnas y bloques con cortes
+/// recursivos en X/Y (XY-cut) y numera los párrafos en ese orden. Con `engine_paragraphs`
+/// se respetan los párrafos que dio el motor; si no, se deducen de la geometría.
+pub fn reading_order(lines: Vec<OcrLine>, engine_paragraphs: bool) -> Vec<OcrLine> {
+    let Some(line_height) = median_height(&lines) else {
+        return lines;
+    };
+
+    let segments: Vec<OcrLine> = lines
+        .into_iter()
+        .flat_map(|line| split_line(line, line_height * COLUMN_GAP))
+        .collect();
+    let mut blocks = Vec::new();
+    cut(segments, line_height, &mut blocks);
+
+    let mut ordered = Vec::new();
+    let mut paragraph = 0;
+    for (i, block) in blocks.into_iter().enumerate() {
+        if i > 0 {
+            paragraph += 1;
+        }
+        let mut previous: Option<(usize, egui::Rect)> = None;
+        for mut line in block {
+            if let Some((prev_paragraph, prev_bbox)) = previous {
+                let new_paragraph = if engine_paragraphs {
+                    line.paragraph != prev_paragraph
+                } else {
+                    line.bbox.min.y - prev_bbox.max.y > line_height * PARAGRAPH_GAP
+                };
+                if new_paragraph {
+                    paragraph += 1;
+                }
+            }
+            previous = Some((line.paragraph, line.bbox));
+            line.paragraph = paragraph;
+            ordered.push(line);
+        }
+    }
+    ordered
+}
+
+fn median_height(lines: &[OcrLine]) -> Option<f32> {
+    let mut heights: Vec<f32> = lines.iter().map(|l| l.bbox.height()).collect();
+    if heights.is_empty() {
+        return None;
+    }
+    heights.sort_by(f32::total_cmp);
+    Some(heights[heights.len() / 2].max(1.0))
+}
+
+fn bounding_box(words: &[OcrWord]) -> egui::Rect {
+    words
+        .iter()
+        .fold(egui::Rect::NOTHING, |acc, w| acc.union(w.bbox))
+}
+
+/// Parte una línea donde hay un hueco demasiado ancho para ser un espacio entre palabras.
+fn split_line(line: OcrLine, min_gap: f32) -> Vec<OcrLine> {
+    let mut segments: Vec<Vec<OcrWord>> = Vec::new();
+    for word in line.words {
+        match segments.last_mut() {
+            Some(current) if word.bbox.min.x - bounding_box(current).max.x <= min_gap => {
+                current.push(word)
+            }
+            _ => segments.push(vec![word]),
+        }
+    }
+    segments
+        .into_iter()
+        .map(|words| OcrLine {
+            bbox: bounding_box(&words),
+            words,
+            paragraph: line.paragraph,
+        })
+        .collect()
+}
+
+/// Ordena por filas y, dentro de cada fila, de izquierda a derecha. Un segmento algo más alto
+/// que sus vecinos sigue en su fila y no se adelanta a los que tiene a la izquierda.
+fn sort_by_position(segments: Vec<OcrLine>, line_height: f32) -> Vec<OcrLine> {
+    let mut segments = segments;
+    segments.sort_by(|a, b| a.bbox.center().y.total_cmp(&b.bbox.center().y));
+
+    let mut rows: Vec<Vec<OcrLine>> = Vec::new();
+    for segment in segments {
+        match rows.last_mut() {
+            Some(row)
+                if segment.bbox.center().y - row[0].bbox.center().y
+                    <= line_height * ROW_TOLERANCE =>
+            {
+                row.push(segment)
+            }
+            _ => rows.push(vec![segment]),
+        }
+    }
+    rows.into_iter()
+        .flat_map(|mut row| {
+            row.sort_by(|a, b| a.bbox.min.x.total_cmp(&b.bbox.min.x));
+            row
+        })
+        .collect()
+}
+
+fn cut(mut segments: Vec<OcrLine>, line_height: f32, blocks: &mut Vec<Vec<OcrLine>>) {
+    if segments.is_empty() {
+        return;
+    }
+    let min_gap = line_height * COLUMN_GAP;
+    segments.sort_by(|a, b| a.bbox.min.y.total_cmp(&b.bbox.min.y));
+
+    // Bandas horizontales separadas por huecos en Y. Las consecutivas que comparten un canal
+    // vertical forman una región de varias columnas; las que no tienen ninguno, un bloque.
+    let mut region: Vec<OcrLine> = Vec::new();
+    let mut region_bands = 0;
+    for band in bands(segments) {
+        if region.is_empty() {
+            region = band;
+            region_bands = 1;
+            continue;
+        }
+        let mut merged = region.clone();
+        merged.extend(band.iter().cloned());
+        let continues = column_gap(&merged, min_gap).is_some()
+            || (column_gap(&region, min_gap).is_none() && column_gap(&band, min_gap).is_none());
+        if continues {
+            region = merged;
+            region_bands += 1;
+        } else {
+            emit_region(
+                std::mem::take(&mut region),
+                region_bands,
+                line_height,
+                blocks,
+            );
+            region = band;
+            region_bands = 1;
+        }
+    }
+    emit_region(region, region_bands, line_height, blocks);
+}
+
+/// Una sola banda no se parte en columnas: un hueco en una línea suelta ("Total:   42")
+/// no basta para considerarlo una columna.
+fn emit_region(
+    region: Vec<OcrLine>,
+    bands: usize,
+    line_height: f32,
+    blocks: &mut Vec<Vec<OcrLine>>,
+) {
+    if region.is_empty() {
+        return;
+    }
+    match column_gap(&region, line_height * COLUMN_GAP).filter(|_| bands > 1) {
+        Some(x) => {
+            let (left, right): (Vec<_>, Vec<_>) = region
+                .into_iter()
+                .partition(|line| line.bbox.center().x < x);
+            cut(left, line_height, blocks);
+            cut(right, line_height, blocks);
+        }
+        None => blocks.push(sort_by_position(region, line_height)),
+    }
+}
+
+/// Agrupa los segmentos (ya ordenados por Y) en bandas sin solapamiento vertical entre sí.
+fn bands(segments: Vec<OcrLine>) -> Vec<Vec<OcrLine>> {
+    let mut bands: Vec<Vec<OcrLine>> = Vec::new();
+    let mut band_bottom = f32::NEG_INFINITY;
+    for segment in segments {
+        if segment.bbox.min.y > band_bottom || bands.is_empty() {
+            bands.push(Vec::new());
+            band_bottom = segment.bbox.max.y;
+        } else {
+            band_bottom = band_bottom.max(segment.bbox.max.y);
+        }
+        bands.last_mut().expect("banda recién creada").push(segment);
+    }
+    bands
+}
+
+/// Centro del hueco vertical más ancho que no cruza ningún segmento, si supera `min_gap`.
+fn column_gap(segments: &[OcrLine], min_gap: f32) -> Option<f32> {
+    let mut ranges: Vec<(f32, f32)> = segments
+        .iter()
+        .map(|s| (s.bbox.min.x, s.bbox.max.x))
+        .collect();
+    ranges.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut best: Option<(f32, f32)> = None;
+    let mut covered_until = ranges.first()?.1;
+    for &(start, end) in &ranges[1..] {
+        let gap = start - covered_until;
+        if gap >= min_gap && best.is_none_or(|(_, width)| gap > width) {
+            best = Some((covered_until + gap / 2.0, gap));
+        }
+        covered_until = covered_until.max(end);
+    }
+    best.map(|(center, _)| center)
+}
+
+/// Texto generado a partir de las líneas.
+pub struct Text {
+    pub text: String,
+    /// Bytes de `text` que ocupa cada palabra, en el orden de las líneas y sus palabras.
+    pub word_spans: Vec<Range<usize>>,
+}
+
+/// Va escribiendo el texto y apunta dónde queda cada palabra.
+struct TextBuilder<'a> {
+    lines: &'a [OcrLine],
+    /// Posición en `word_spans` de la primera palabra de cada línea.
+    first_word: Vec<usize>,
+    text: String,
+    word_spans: Vec<Range<usize>>,
+}
+
+impl<'a> TextBuilder<'a> {
+    fn new(lines: &'a [OcrLine]) -> Self {
+        let first_word = lines
+            .iter()
+            .scan(0, |next, line| {
+                let first = *next;
+                *next += line.words.len();
+                Some(first)
+            })
+            .collect();
+        let words = lines.iter().map(|line| line.words.len()).sum();
+        TextBuilder {
+            lines,
+            first_word,
+            text: String::new(),
+            word_spans: vec![0..0; words],
+        }
+    }
+
+    /// Escribe la línea `index` con sus palabras separadas por un espacio.
+    fn push_line(&mut self, index: usize) {
+        for (i, word) in self.lines[index].words.iter().enumerate() {
+            if i > 0 {
+                self.text.push(' ');
+            }
+            let start = self.text.len();
+            self.text.push_str(word.text.trim());
+            self.word_spans[self.first_word[index] + i] = start..self.text.len();
+        }
+    }
+}
+
+pub fn to_text(lines: &[OcrLine], mode: TextMode) -> Text {
+    let mut builder = TextBuilder::new(lines);
+    match mode {
+        TextMode::SingleLine | TextMode::Lines | TextMode::Paragraphs => {
+            for (i, line) in lines.iter().enumerate() {
+                if i > 0 {
+                    builder.text.push_str(match mode {
+                        TextMode::Lines => "\n",
+                        TextMode::Paragraphs if lines[i - 1].paragraph != line.paragraph => "\n\n",
+                        _ => " ",
+                    });
+                }
+                builder.push_line(i);
+            }
+        }
+        TextMode::Layout => layout_text(&mut builder),
+    }
+    Text {
+        text: builder.text,
+        word_spans: builder.word_spans,
+    }
+}
+
+/// Ancho medio de un carácter, para pasar posiciones en píxeles a columnas de texto.
+fn char_width(lines: &[OcrLine], line_height: f32) -> f32 {
+    let (width, chars) =
+        lines
+            .iter()
+            .flat_map(|line| &line.words)
+            .fold((0.0, 0usize), |(width, chars), word| {
+                (
+                    width + word.bbox.width(),
+                    chars + word.text.trim().chars().count(),
+                )
+            });
+    if chars == 0 {
+        line_height / 2.0
+    } else {
+        (width / chars as f32).max(1.0)
+    }
+}
+
+/// Coloca cada línea en su fila y columna aproximadas, como `pdftotext -layout`.
+fn layout_text(builder: &mut TextBuilder) {
+    let lines = builder.lines;
+    let Some(line_height) = median_height(lines) else {
+        return;
+    };
+    let char_width = char_width(lines, line_height);
+    let left = lines
+        .iter()
+        .map(|l| l.bbox.min.x)
+        .fold(f32::INFINITY, f32::min);
+
+    let mut sorted: Vec<(usize, &OcrLine)> = lines.iter().enumerate().collect();
+    sorted.sort_by(|(_, a), (_, b)| a.bbox.center().y.total_cmp(&b.bbox.center().y));
+
+    // Filas: líneas cuyo centro cae dentro de la altura de la fila actual.
+    let mut rows: Vec<(egui::Rangef, Vec<(usize, &OcrLine)>)> = Vec::new();
+    for (index, line) in sorted {
+        match rows.last_mut() {
+            Some((range, row)) if range.contains(line.bbox.center().y) => {
+                range.min = range.min.min(line.bbox.min.y);
+                range.max = range.max.max(line.bbox.max.y);
+                row.push((index, line));
+            }
+            _ => rows.push((line.bbox.y_range(), vec![(index, line)])),
+        }
+    }
+
+    let mut previous_bottom: Option<f32> = None;
+    for (range, mut row) in rows {
+        if let Some(bottom) = previous_bottom {
+            builder.text.push('\n');
+            let blank = ((range.min - bottom) / line_height).floor().max(0.0) as usize;
+            builder
+                .text
+                .push_str(&"\n".repeat(blank.min(MAX_BLANK_LINES)));
+        }
+        previous_bottom = Some(range.max);
+
+        row.sort_by(|(_, a), (_, b)| a.bbox.min.x.total_cmp(&b.bbox.min.x));
+        let row_start = builder.text.len();
+        for (index, line) in row {
+            let column = ((line.bbox.min.x - left) / char_width).round().max(0.0) as usize;
+            let current = builder.text[row_start..].chars().count();
+            if current < column {
+                builder.text.push_str(&" ".repeat(column - current));
+            } else if current > 0 {
+                builder.text.push(' ');
+            }
+            builder.push_line(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(lines: &[OcrLine]) -> Vec<&str> {
+        lines.iter().map(|l| l.words[0].text.as_str()).collect()
+    }
+
+    #[test]
+    fn reads_columns_top_to_bottom() {
+        let lines = vec![
+            OcrLine::test(vec![OcrWord::test("a1", 0.0, 0.0, 100.0, 20.0)], 0),
+            OcrLine::test(vec![OcrWord::test("b1", 200.0, 0.0, 100.0, 20.0)], 0),
+            OcrLine::test(vec![OcrWord::test("a2", 0.0, 25.0, 100.0, 20.0)], 0),
+            OcrLine::test(vec![OcrWord::test("b2", 200.0, 25.0, 100.0, 20.0)], 0),
+            OcrLine::test(vec![OcrWord::test("a3", 0.0, 50.0, 100.0, 20.0)], 0),
+            OcrLine::test(vec![OcrWord::test("b3", 200.0, 50.0, 100.0, 20.0)], 0),
+        ];
+        let ordered = reading_order(lines, false);
+        assert_eq!(texts(&ordered), ["a1", "a2", "a3", "b1", "b2", "b3"]);
+        assert_ne!(ordered[2].paragraph, ordered[3].paragraph);
+    }
+
+    #[test]
+    fn single_band_is_not_split_into_columns() {
+        let lines = vec![
+            OcrLine::test(vec![OcrWord::test("Total:", 0.0, 0.0, 60.0, 20.0)], 0),
+            OcrLine::test(vec![OcrWord::test("42", 300.0, 0.0, 20.0, 20.0)], 0),
+        ];
+        assert_eq!(texts(&reading_order(lines, false)), ["Total:", "42"]);
+    }
+
+    #[test]
+    fn taller_segment_keeps_its_row() {
+        // "b" empieza un poco más arriba que "a", pero está en la misma fila y a su derecha.
+        let lines = vec![
+            OcrLine::test(vec![OcrWord::test("b", 60.0, 0.0, 40.0, 20.0)], 0),
+            OcrLine::test(vec![OcrWord::test("a", 0.0, 4.0, 40.0, 20.0)], 0),
+            OcrLine::test(vec![OcrWord::test("c", 0.0, 30.0, 40.0, 20.0)], 0),
+        ];
+        assert_eq!(texts(&reading_order(lines, false)), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn keeps_engine_paragraphs() {
+        let lines = vec![
+            OcrLine::test(vec![OcrWord::test("uno", 0.0, 0.0, 100.0, 20.0)], 3),
+            OcrLine::test(vec![OcrWord::test("dos", 0.0, 22.0, 100.0, 20.0)], 3),
+            OcrLine::test(vec![OcrWord::test("tres", 0.0, 44.0, 100.0, 20.0)], 7),
+        ];
+        let ordered = reading_order(lines.clone(), true);
+        assert_eq!(ordered[0].paragraph, ordered[1].paragraph);
+        assert_ne!(ordered[1].paragraph, ordered[2].paragraph);
+
+        // Sin párrafos del motor, las líneas juntas forman uno solo.
+        let ordered = reading_order(lines, false);
+        assert!(ordered.iter().all(|l| l.paragraph == ordered[0].paragraph));
+    }
+
+    #[test]
+    fn vertical_gap_starts_paragraph() {
+        let lines = vec![
+            OcrLine::test(vec![OcrWord::test("uno", 0.0, 0.0, 100.0, 20.0)], 0),
+            OcrLine::test(vec![OcrWord::test("dos", 0.0, 22.0, 100.0, 20.0)], 0),
+            OcrLine::test(vec![OcrWord::test("tres", 0.0, 80.0, 100.0, 20.0)], 0),
+        ];
+        let ordered = reading_order(lines, false);
+        assert_eq!(ordered[0].paragraph, ordered[1].paragraph);
+        assert_ne!(ordered[1].paragraph, ordered[2].paragraph);
+        assert_eq!(
+            to_text(&ordered, TextMode::Paragraphs).text,
+            "uno dos\n\ntres"
+        );
+        assert_eq!(to_text(&ordered, TextMode::Lines).text, "uno\ndos\ntres");
+        assert_eq!(to_text(&ordered, TextMode::SingleLine).text, "uno dos tres");
+    }
+
+    #[test]
+    fn splits_line_at_wide_gap() {
+        let wide = OcrLine::test(
+            vec![
+                OcrWord::test("a", 0.0, 0.0, 20.0, 20.0),
+                OcrWord::test("b", 200.0, 0.0, 20.0, 20.0),
+            ],
+            0,
+        );
+        assert_eq!(split_line(wide.clone(), 24.0).len(), 2);
+        assert_eq!(split_line(wide, 500.0).len(), 1);
+    }
+
+    #[test]
+    fn word_spans_follow_the_text() {
+        let lines = vec![
+            OcrLine::test(
+                vec![
+                    OcrWord::test("  Hola", 0.0, 0.0, 40.0, 20.0),
+                    OcrWord::test("mundo", 45.0, 0.0, 40.0, 20.0),
+                ],
+                0,
+            ),
+            OcrLine::test(vec![OcrWord::test("adiós", 20.0, 40.0, 40.0, 20.0)], 1),
+        ];
+
+        for mode in TextMode::ALL {
+            let text = to_text(&lines, mode);
+            let words: Vec<&str> = text
+                .word_spans
+                .iter()
+                .map(|s| &text.text[s.clone()])
+                .collect();
+            assert_eq!(words, ["Hola", "mundo", "adiós"], "{:?}", mode);
+        }
+        assert_eq!(
+            to_text(&lines, TextMode::Layout).text,
+            "Hola mundo\n\n  adiós"
+        );
+    }
+}
@@ -1,16 +1,66 @@
+use clap::Parser;
 use eframe::egui;
-use image::ImageEncoder;
-use image::imageops::FilterType;
 use image::{DynamicImage, RgbaImage};
 use lazy_static::lazy_static;
 use libwayshot::WayshotConnection;
-use std::io::{self, Write};
-use std::process::{Command, Stdio};
-use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::io;
+use std::ops::Range;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(any(feature = "paddleocr", feature = "ollama", feature = "gemini"))]
+use std::sync::mpsc;
+#[cfg(any(feature = "ollama", feature = "gemini"))]
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::{Duration, Instant};
 use tokio::runtime::Runtime;
 
+#[cfg(all(feature = "tesseract", not(feature = "tesseract_api")))]
+use rusty_tesseract::Image as TessImage;
 #[cfg(feature = "tesseract")]
-use rusty_tesseract::{Args, Image as TessImage};
+use rusty_tesseract::{Args, Data};
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+/// Con `--autocopy`, stdout queda solo para el texto de `--print`.
+static AUTOCOPY: AtomicBool = AtomicBool::new(false);
+
+/// Igual que `eprintln!`, pero solo con `--verbose`.
+macro_rules! verbose {
+    ($($arg:tt)*) => {
+        if crate::VERBOSE.load(std::sync::atomic::Ordering::Relaxed) {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+/// Igual que `println!`, salvo con `--autocopy`.
+macro_rules! report {
+    ($($arg:tt)*) => {
+        if !crate::AUTOCOPY.load(std::sync::atomic::Ordering::Relaxed) {
+            println!($($arg)*);
+        }
+    };
+}
+
+mod capture;
+mod cli;
+mod clipboard;
+mod config;
+mod notify;
+mod preprocess;
+mod quad;
+mod tiles;
+
+#[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+mod export;
+
+#[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+mod layout;
+
+#[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+mod pdf;
+
+#[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+mod table;
 
 #[cfg(feature = "gemini")]
 mod gemini;
@@ -21,32 +71,63 @@ mod ollama;
 #[cfg(feature = "paddleocr")]
 mod paddle_client;
 
+#[cfg(feature = "paddle_onnx")]
+mod paddle_onnx;
+
+#[cfg(feature = "tesseract_api")]
+mod tesseract_api;
+
 lazy_static! {
     static ref TOKIO_RUNTIME: Runtime = Runtime::new().expect("Failed to create Tokio runtime");
 }
 
-fn copy_text_with_wl_copy(text: &str) -> io::Result<()> {
-    let mut child = Command::new("wl-copy")
-        .stdin(Stdio::piped())
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .spawn()
-        .map_err(|e| {
-            if e.kind() == io::ErrorKind::NotFound {
-                io::Error::new(
-                    io::ErrorKind::NotFound,
-                    "Comando 'wl-copy' no encontrado. ¿Está instalado 'wl-clipboard'?",
-                )
-            } else {
-                e
-            }
-        })?;
+/// Confianza por debajo de la cual se resaltan las palabras en el editor de resultados.
+#[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+const LOW_CONFIDENCE: f32 = 60.0;
+/// Pausa tras el último cambio de los ajustes antes de regenerar la vista previa.
+const PREVIEW_DELAY: Duration = Duration::from_millis(300);
+
+/// Rojo con confianza 0, amarillo con 50 y verde con 100.
+fn confidence_color(confidence: f32) -> egui::Color32 {
+    let t = (confidence / 100.0).clamp(0.0, 1.0);
+    egui::Color32::from_rgb(
+        ((1.0 - t) * 2.0 * 255.0).min(255.0) as u8,
+        (t * 2.0 * 255.0).min(255.0) as u8,
+        0,
+    )
+}
 
-    if let Some(mut stdin) = child.stdin.take() {
-        stdin.write_all(text.as_bytes())?;
-    }
+/// Texto del editor de resultados con fondo rojo en los rangos de bytes de `highlighted`,
+/// ordenados y sin solaparse.
+fn highlight_ranges(
+    ui: &egui::Ui,
+    text: &str,
+    wrap_width: f32,
+    highlighted: &[Range<usize>],
+) -> Arc<egui::Galley> {
+    let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+    let plain = egui::TextFormat::simple(font_id, ui.visuals().text_color());
+    let marked = egui::TextFormat {
+        background: egui::Color32::from_rgba_unmultiplied(255, 80, 80, 70),
+        ..plain.clone()
+    };
 
-    Ok(())
+    let mut job = egui::text::LayoutJob::default();
+    job.wrap.max_width = wrap_width;
+    let mut position = 0;
+    for range in highlighted {
+        let Some(word) = text.get(range.clone()) else {
+            continue;
+        };
+        if range.start < position {
+            continue;
+        }
+        job.append(&text[position..range.start], 0.0, plain.clone());
+        job.append(word, 0.0, marked.clone());
+        position = range.end;
+    }
+    job.append(&text[position..], 0.0, plain);
+    ui.fonts(|fonts| fonts.layout_job(job))
 }
 
 trait RectExt {
@@ -72,30 +153,133 @@ enum DragMode {
     TopRight,
     BottomLeft,
     BottomRight,
+    /// Arrastrando sobre las palabras reconocidas para seleccionar texto.
+    SelectingText,
 }
 
 #[derive(Debug, Clone)]
 struct OcrWord {
     text: String,
     bbox: egui::Rect,
+    /// Confianza del motor, de 0 a 100; `None` si el motor no la da.
+    confidence: Option<f32>,
+    /// Contorno exacto cuando el motor lo da (PaddleOCR); `bbox` es su caja envolvente.
+    polygon: Option<quad::Quad>,
 }
 
 #[derive(Debug, Clone)]
 struct OcrLine {
     words: Vec<OcrWord>,
     bbox: egui::Rect,
+    paragraph: usize,
+}
+
+impl OcrWord {
+    fn quad(&self) -> quad::Quad {
+        self.polygon.unwrap_or_else(|| quad::from_rect(self.bbox))
+    }
+}
+
+#[cfg(all(test, any(feature = "tesseract", feature = "paddleocr")))]
+impl OcrWord {
+    /// Palabra de prueba con la caja indicada y una confianza del 90 %.
+    fn test(text: &str, x: f32, y: f32, width: f32, height: f32) -> OcrWord {
+        OcrWord {
+            text: text.to_string(),
+            bbox: egui::Rect::from_min_size(egui::pos2(x, y), egui::vec2(width, height)),
+            confidence: Some(90.0),
+            polygon: None,
+        }
+    }
+}
+
+#[cfg(all(test, any(feature = "tesseract", feature = "paddleocr")))]
+impl OcrLine {
+    /// Línea de prueba cuya caja envuelve sus palabras.
+    fn test(words: Vec<OcrWord>, paragraph: usize) -> OcrLine {
+        let bbox = words
+            .iter()
+            .fold(egui::Rect::NOTHING, |acc, w| acc.union(w.bbox));
+        OcrLine {
+            words,
+            bbox,
+            paragraph,
+        }
+    }
+}
+
+impl OcrLine {
+    /// Contorno orientado de la línea, si alguna de sus palabras tiene polígono.
+    fn outline(&self) -> Option<quad::Quad> {
+        let first = self.words.iter().find_map(|w| w.polygon)?;
+        Some(quad::oriented_bounds(
+            self.words.iter().flat_map(|w| w.quad()),
+            quad::direction(&first),
+        ))
+    }
 }
 
 struct ScreenshotApp {
     screenshot_image: RgbaImage,
-    texture_handle: egui::TextureHandle,
+    screenshot_texture: tiles::TiledTexture,
+    /// Área (en puntos de egui) donde se dibuja la captura; las selecciones están en este espacio.
+    /// Si solo se ve una salida, el resto de la captura queda fuera de la ventana.
+    image_rect: egui::Rect,
+    monitors: Vec<capture::Monitor>,
+    /// Ya se avisó de que no se puede saber en qué salida está la ventana.
+    ambiguous_monitor_warned: bool,
     selection: Option<egui::Rect>,
     drag_start: Option<egui::Pos2>,
     drag_mode: DragMode,
     ocr_results: Vec<OcrWord>,
     ocr_lines: Vec<OcrLine>,
+    /// Líneas tal como las devuelve el motor, antes de filtrar por confianza.
+    #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+    raw_lines: Vec<OcrLine>,
+    /// `raw_lines` trae los párrafos del motor (Tesseract); si no, los deduce `layout`.
+    #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+    engine_paragraphs: bool,
+    /// Palabras seleccionadas en la capa de texto (índices de `ocr_results`, en orden de lectura).
+    text_selection: Option<(usize, usize)>,
+    #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+    table: Option<table::Table>,
+    /// Archivos de `--hocr`, `--alto`, `--pdf` y `--json`, que se reescriben tras cada
+    /// reconocimiento y al cambiar el modo de texto o la confianza mínima.
+    #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+    exports: Vec<(export::Format, std::path::PathBuf)>,
+    /// Motor, modelo y tiempos del último reconocimiento, para la exportación en JSON.
+    #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+    recognition: export::Metadata,
+    /// Posición lógica de la captura en el escritorio y píxeles por punto lógico.
+    #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+    capture_origin: egui::Pos2,
+    #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+    capture_scale: egui::Vec2,
     results: String,
+    /// Rangos de `results` con palabras de baja confianza. Solo valen mientras `results` siga
+    /// igual que `generated_results`, es decir, hasta que se edite el texto.
+    low_confidence: Vec<Range<usize>>,
+    generated_results: String,
     menu_anchor_pos: Option<egui::Pos2>,
+    /// Ruta en edición tras pulsar "Save image…".
+    save_image_path: Option<String>,
+    /// Con `--autocopy`, hay un reconocimiento en curso cuyo resultado se copiará al terminar.
+    autocopy_pending: bool,
+    /// El último reconocimiento terminó con un error; `results` tiene el mensaje, no texto.
+    recognition_failed: bool,
+    /// `--print`: el resultado de `--autocopy` también se escribe en stdout.
+    print_result: bool,
+
+    config: config::Config,
+    profile_name: String,
+    /// Perfil de la sesión, con `overrides` aplicados.
+    profile: config::Profile,
+    overrides: config::Overrides,
+    preview_texture: Option<egui::TextureHandle>,
+    /// Último cambio de los ajustes aún no reflejado en la vista previa.
+    preview_changed: Option<Instant>,
+    /// Último motor lanzado sobre la selección; el giro manual lo vuelve a ejecutar.
+    last_engine: Option<config::Engine>,
 
     #[cfg(any(
         feature = "ollama",
@@ -105,54 +289,130 @@ struct ScreenshotApp {
     ))]
     is_ai_working: bool,
     #[cfg(any(feature = "ollama", feature = "ollama_translate", feature = "gemini"))]
-    ai_result_receiver: Option<Receiver<String>>,
+    ai_result_receiver: Option<Receiver<Result<String, String>>>,
 
     #[cfg(feature = "ollama")]
     ollama: ollama::OllamaClient,
     #[cfg(feature = "gemini")]
     gemini: gemini::GeminiClient,
-    #[cfg(feature = "paddleocr")]
-    paddle_client: paddle_client::PaddleClient,
+    #[cfg(all(feature = "paddleocr", not(feature = "paddle_onnx")))]
+    paddle: paddle_client::PaddleClient,
+    #[cfg(feature = "paddle_onnx")]
+    paddle: paddle_onnx::PaddleOnnx,
 
     #[cfg(feature = "tesseract")]
     tesseract_args: Args,
     #[cfg(feature = "tesseract")]
     tesseract_langs: std::vec::Vec<String>,
+    #[cfg(feature = "tesseract_api")]
+    tesseract_api: tesseract_api::TesseractApi,
 }
 
 impl ScreenshotApp {
-    fn new(cc: &eframe::CreationContext<'_>, image: RgbaImage) -> Self {
-        let color_image = egui::ColorImage::from_rgba_unmultiplied(
-            [image.width() as usize, image.height() as usize],
-            &image,
-        );
-        let texture_handle = cc.egui_ctx.load_texture(
-            "screenshot-texture",
-            color_image,
-            egui::TextureOptions::LINEAR,
+    fn new(
+        cc: &eframe::CreationContext<'_>,
+        capture: capture::Capture,
+        config: config::Config,
+        cli: cli::Cli,
+    ) -> Self {
+        let image = capture.image;
+        let screenshot_texture = tiles::TiledTexture::new(&cc.egui_ctx, &image);
+
+        let image_rect = egui::Rect::from_min_size(
+            egui::Pos2::ZERO,
+            egui::vec2(image.width() as f32, image.height() as f32)
+                / cc.egui_ctx.pixels_per_point(),
         );
 
+        let overrides = cli.overrides();
+        let mut profile = config.profile(&cli.profile);
+        overrides.apply(&mut profile);
+        AUTOCOPY.store(profile.autocopy, Ordering::Relaxed);
+        if !profile.engine.is_available() {
+            eprintln!(
+                "Advertencia: el motor elegido no está compilado; se usa el primero disponible."
+            );
+        }
+
         #[cfg(feature = "tesseract")]
-        let tesseract_args = Args {
-            lang: "eng".to_string(),
+        let tesseract_langs = rusty_tesseract::get_tesseract_langs().unwrap_or_default();
+        #[cfg(feature = "tesseract")]
+        {
+            if profile.tesseract_langs.is_empty() {
+                profile.tesseract_langs.push("eng".to_string());
+            }
+            for lang in Self::missing_langs(&profile.tesseract_langs, &tesseract_langs) {
+                eprintln!(
+                    "Advertencia: no está instalado '{}.traineddata' para Tesseract.",
+                    lang
+                );
+            }
+        }
+        #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+        let exports = [
+            (export::Format::Hocr, cli.hocr),
+            (export::Format::Alto, cli.alto),
+            (export::Format::Pdf, cli.pdf),
+            (export::Format::Json, cli.json),
+        ]
+        .into_iter()
+        .filter_map(|(format, path)| Some((format, path?)))
+        .collect();
+        #[cfg(feature = "tesseract")]
+        let mut tesseract_args = Args {
+            lang: profile.tesseract_langs.join("+"),
             psm: Some(6),
             oem: Some(3),
             dpi: Some(150),
             ..Default::default()
         };
         #[cfg(feature = "tesseract")]
-        let tesseract_langs = rusty_tesseract::get_tesseract_langs().unwrap_or_default();
+        Self::apply_char_preset(&mut tesseract_args, &profile);
 
         Self {
             screenshot_image: image,
-            texture_handle,
+            screenshot_texture,
+            image_rect,
+            monitors: capture.monitors,
+            ambiguous_monitor_warned: false,
             selection: None,
             drag_start: None,
             drag_mode: DragMode::default(),
             ocr_results: Vec::new(),
             ocr_lines: Vec::new(),
+            #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+            raw_lines: Vec::new(),
+            #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+            engine_paragraphs: false,
+            text_selection: None,
+            #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+            table: None,
+            #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+            exports,
+            #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+            recognition: export::Metadata::default(),
+            #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+            capture_origin: capture.origin,
+            #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+            capture_scale: capture.scale,
             results: String::new(),
+            low_confidence: Vec::new(),
+            generated_results: String::new(),
             menu_anchor_pos: None,
+            save_image_path: None,
+            autocopy_pending: false,
+            recognition_failed: false,
+            print_result: cli.print,
+
+            #[cfg(feature = "paddle_onnx")]
+            paddle: paddle_onnx::PaddleOnnx::new(config.paddle_model_dir()),
+            config,
+            profile_name: cli.profile,
+            profile,
+            overrides,
+            preview_texture: None,
+            preview_changed: None,
+            last_engine: None,
 
             #[cfg(any(feature = "ollama", feature = "gemini", feature = "paddleocr"))]
             is_ai_working: false,
@@ -163,13 +423,211 @@ impl ScreenshotApp {
             ollama: ollama::OllamaClient::new(),
             #[cfg(feature = "gemini")]
             gemini: gemini::GeminiClient::new(),
-            #[cfg(feature = "paddleocr")]
-            paddle_client: paddle_client::PaddleClient::new(),
+            #[cfg(all(feature = "paddleocr", not(feature = "paddle_onnx")))]
+            paddle: paddle_client::PaddleClient::new(),
 
             #[cfg(feature = "tesseract")]
             tesseract_args,
             #[cfg(feature = "tesseract")]
             tesseract_langs,
+            #[cfg(feature = "tesseract_api")]
+            tesseract_api: tesseract_api::TesseractApi::new(),
+        }
+    }
+
+    /// Guarda el perfil sin los cambios que solo pidió la línea de órdenes.
+    fn save_profile(&mut self) {
+        let saved = self.config.profile(&self.profile_name);
+        let profile = self.overrides.persistent(&self.profile, &saved);
+        self.config
+            .profiles
+            .insert(self.profile_name.clone(), profile);
+        if let Err(e) = self.config.save() {
+            eprintln!("Error al guardar el perfil '{}': {}", self.profile_name, e);
+        }
+    }
+
+    #[cfg(feature = "tesseract")]
+    fn missing_langs<'a>(selected: &'a [String], installed: &[String]) -> Vec<&'a String> {
+        selected
+            .iter()
+            .filter(|lang| !installed.contains(lang))
+            .collect()
+    }
+
+    #[cfg(feature = "tesseract")]
+    fn apply_char_preset(args: &mut Args, profile: &config::Profile) {
+        const WHITELIST: &str = "tessedit_char_whitelist";
+        const BLACKLIST: &str = "tessedit_char_blacklist";
+        const USER_PATTERNS: &str = "user_patterns_file";
+
+        for key in [WHITELIST, BLACKLIST, USER_PATTERNS] {
+            args.config_variables.remove(key);
+        }
+
+        let vars = &mut args.config_variables;
+        match profile.tesseract_chars {
+            config::CharPreset::None => {}
+            config::CharPreset::Custom => {
+                if !profile.tesseract_whitelist.is_empty() {
+                    vars.insert(WHITELIST.into(), profile.tesseract_whitelist.clone());
+                }
+                if !profile.tesseract_blacklist.is_empty() {
+                    vars.insert(BLACKLIST.into(), profile.tesseract_blacklist.clone());
+                }
+                if let Some(patterns) = &profile.tesseract_user_patterns {
+                    vars.insert(USER_PATTERNS.into(), patterns.display().to_string());
+                }
+            }
+            preset => {
+                if let Some(whitelist) = preset.whitelist() {
+                    vars.insert(WHITELIST.into(), whitelist.to_string());
+                }
+            }
+        }
+    }
+
+    /// Parte de la captura (en píxeles) que corresponde al monitor donde está la ventana.
+    /// Con varias salidas se identifica por su tamaño y su escala; si no se puede, se muestra todo.
+    fn visible_region(&mut self, ctx: &egui::Context) -> egui::Rect {
+        let full = egui::Rect::from_min_size(
+            egui::Pos2::ZERO,
+            egui::vec2(
+                self.screenshot_image.width() as f32,
+                self.screenshot_image.height() as f32,
+            ),
+        );
+        if self.monitors.len() < 2 {
+            return full;
+        }
+        let (outer_rect, monitor_size, native_scale) = ctx.input(|i| {
+            let viewport = i.viewport();
+            (
+                viewport.outer_rect,
+                viewport.monitor_size,
+                viewport.native_pixels_per_point,
+            )
+        });
+        // Si se conoce la posición de la ventana (X11), la salida es la que contiene su centro.
+        if let Some(outer_rect) = outer_rect
+            && let Some(monitor) = self
+                .monitors
+                .iter()
+                .find(|m| m.logical_rect.contains(outer_rect.center()))
+        {
+            return monitor.rect;
+        }
+        // En Wayland solo se conocen el tamaño y la escala del monitor.
+        let Some(monitor_size) = monitor_size else {
+            return full;
+        };
+        let same_size: Vec<&capture::Monitor> = self
+            .monitors
+            .iter()
+            .filter(|m| (m.logical_rect.size() - monitor_size).length() < 2.0)
+            .collect();
+        let candidates: Vec<&capture::Monitor> = match native_scale {
+            Some(native_scale) if same_size.len() > 1 => same_size
+                .iter()
+                .copied()
+                .filter(|m| (m.scale - native_scale).abs() < 0.01)
+                .collect(),
+            _ => same_size,
+        };
+        match candidates.as_slice() {
+            [monitor] => monitor.rect,
+            _ => {
+                if !candidates.is_empty() && !self.ambiguous_monitor_warned {
+                    self.ambiguous_monitor_warned = true;
+                    eprintln!(
+                        "[captura] Hay varias salidas de {}x{} con la misma escala y no se sabe en \
+                         cuál está la ventana; se muestra la captura completa. Usa --monitor para \
+                         elegir una.",
+                        monitor_size.x, monitor_size.y
+                    );
+                }
+                full
+            }
+        }
+    }
+
+    fn draw_screenshot(&mut self, ui: &mut egui::Ui) {
+        let window = ui.max_rect();
+        let visible = self.visible_region(ui.ctx());
+        let image_size = egui::vec2(
+            self.screenshot_image.width() as f32,
+            self.screenshot_image.height() as f32,
+        );
+        self.screenshot_texture.paint(ui.painter(), visible, window);
+
+        let scale = window.size() / visible.size();
+        self.image_rect = egui::Rect::from_min_size(
+            window.min - visible.min.to_vec2() * scale,
+            image_size * scale,
+        );
+    }
+
+    fn draw_preprocess_settings(&mut self, ui: &mut egui::Ui) {
+        let before = self.profile.preprocess.clone();
+        let settings = &mut self.profile.preprocess;
+        // Mientras se arrastra un deslizador o se escribe su valor, el cambio aún no se guarda.
+        let mut settled = false;
+        let mut track = |response: egui::Response| {
+            settled |= response.drag_stopped()
+                || response.lost_focus()
+                || (response.changed() && !response.dragged() && !response.has_focus());
+        };
+
+        track(ui.checkbox(&mut settings.deskew, "Deskew"));
+        track(ui.checkbox(&mut settings.grayscale, "Grayscale"));
+        track(ui.checkbox(&mut settings.auto_scale, "Auto scale from text height"));
+        track(ui.add_enabled(
+            !settings.auto_scale,
+            egui::Slider::new(&mut settings.scale, 1.0..=4.0).text("Scale"),
+        ));
+        track(ui.add(egui::Slider::new(&mut settings.contrast, 0.0..=100.0).text("Contrast")));
+        track(ui.add(egui::Slider::new(&mut settings.denoise, 0.0..=3.0).text("Denoise")));
+        track(ui.add(egui::Slider::new(&mut settings.sharpen, 0.0..=3.0).text("Sharpen")));
+        track(ui.checkbox(&mut settings.auto_invert, "Auto-invert dark backgrounds"));
+        track(ui.checkbox(&mut settings.invert, "Invert"));
+        egui::ComboBox::from_label("Threshold")
+            .selected_text(settings.threshold.label())
+            .show_ui(ui, |ui| {
+                for threshold in preprocess::Threshold::ALL {
+                    track(ui.selectable_value(
+                        &mut settings.threshold,
+                        threshold,
+                        threshold.label(),
+                    ));
+                }
+            });
+        match settings.threshold {
+            preprocess::Threshold::Fixed => {
+                track(
+                    ui.add(egui::Slider::new(&mut settings.threshold_level, 0..=255).text("Level")),
+                );
+            }
+            preprocess::Threshold::Adaptive => {
+                track(
+                    ui.add(egui::Slider::new(&mut settings.adaptive_radius, 1..=50).text("Radius")),
+                );
+            }
+            _ => {}
+        }
+        #[cfg(any(feature = "paddleocr", feature = "ollama", feature = "gemini"))]
+        track(ui.checkbox(&mut settings.all_engines, "Apply to all engines"));
+
+        if self.profile.preprocess != before {
+            self.preview_changed = Some(Instant::now());
+        }
+        if settled {
+            self.save_profile();
+        }
+
+        if let Some(texture) = self.preprocess_preview(ui.ctx()) {
+            let size = texture.size_vec2();
+            let scale = (300.0 / size.x).min(1.0);
+            ui.image((texture.id(), size * scale));
         }
     }
 
@@ -200,19 +658,115 @@ impl ScreenshotApp {
                 });
                 ui.separator();
 
+                ui.horizontal(|ui| {
+                    ui.label("Rotate:");
+                    let mut rotation = self.profile.preprocess.rotation;
+                    for degrees in [0, 90, 180, 270] {
+                        ui.selectable_value(&mut rotation, degrees, format!("{}°", degrees));
+                    }
+                    if rotation != self.profile.preprocess.rotation {
+                        self.profile.preprocess.rotation = rotation;
+                        self.preview_texture = None;
+                        self.run_engine(self.active_engine());
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    if ui.button("Save image…").clicked() {
+                        self.save_image_path = Some(self.default_image_path());
+                    }
+                    if ui.button("Copy image").clicked() {
+                        self.copy_image();
+                    }
+                });
+                if let Some(mut path) = self.save_image_path.take() {
+                    let mut keep = true;
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut path);
+                        if ui.button("Save").clicked() {
+                            self.save_image(std::path::Path::new(&path));
+                            keep = false;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            keep = false;
+                        }
+                    });
+                    if keep {
+                        self.save_image_path = Some(path);
+                    }
+                }
+
+                ui.collapsing("Preprocessing", |ui| {
+                    self.draw_preprocess_settings(ui);
+                });
+
                 #[cfg(feature = "tesseract")]
                 ui.collapsing("Tesseract Config", |ui| {
                     let mut selected_psm = self.tesseract_args.psm.unwrap_or(3);
                     let mut selected_oem = self.tesseract_args.oem.unwrap_or(3);
-                    let cur_lang = &mut self.tesseract_args.lang;
-                    ui.label("Tesseract Lang");
+                    ui.label("Tesseract Langs");
+                    let selected_langs = &self.profile.tesseract_langs;
+                    let (mut move_up, mut move_down, mut remove, mut add) =
+                        (None, None, None, None);
+                    for (i, lang) in selected_langs.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("{}. {}", i + 1, lang));
+                            if ui.add_enabled(i > 0, egui::Button::new("⬆")).clicked() {
+                                move_up = Some(i);
+                            }
+                            if ui
+                                .add_enabled(i + 1 < selected_langs.len(), egui::Button::new("⬇"))
+                                .clicked()
+                            {
+                                move_down = Some(i);
+                            }
+                            if ui
+                                .add_enabled(selected_langs.len() > 1, egui::Button::new("❌"))
+                                .clicked()
+                            {
+                                remove = Some(i);
+                            }
+                        });
+                    }
+                    for lang in Self::missing_langs(selected_langs, &self.tesseract_langs) {
+                        ui.colored_label(
+                            egui::Color32::YELLOW,
+                            format!("⚠ {}.traineddata is not installed", lang),
+                        );
+                    }
                     egui::ComboBox::from_id_source("lang_select")
-                        .selected_text(cur_lang.as_str())
+                        .selected_text("Add language…")
                         .show_ui(ui, |ui| {
                             for lang in &self.tesseract_langs {
-                                ui.selectable_value(cur_lang, lang.clone(), lang);
+                                if !selected_langs.contains(lang)
+                                    && ui.selectable_label(false, lang).clicked()
+                                {
+                                    add = Some(lang.clone());
+                                }
                             }
                         });
+
+                    let changed = move_up.is_some()
+                        || move_down.is_some()
+                        || remove.is_some()
+                        || add.is_some();
+                    let langs = &mut self.profile.tesseract_langs;
+                    if let Some(i) = move_up {
+                        langs.swap(i, i - 1);
+                    }
+                    if let Some(i) = move_down {
+                        langs.swap(i, i + 1);
+                    }
+                    if let Some(i) = remove {
+                        langs.remove(i);
+                    }
+                    if let Some(lang) = add {
+                        langs.push(lang);
+                    }
+                    if changed {
+                        self.tesseract_args.lang = langs.join("+");
+                        self.save_profile();
+                    }
                     ui.label("PSM (Page Segmentation Mode):");
                     egui::ComboBox::from_id_source("psm_select")
                         .selected_text(format!("{}", selected_psm))
@@ -239,6 +793,63 @@ impl ScreenshotApp {
                                 }
                             }
                         });
+                    ui.label("Characters:");
+                    // Los campos de texto se aplican al escribir, pero solo se guardan al salir
+                    // de ellos para no reescribir config.json con cada tecla.
+                    let (mut chars_changed, mut save_chars) = (false, false);
+                    egui::ComboBox::from_id_source("chars_select")
+                        .selected_text(self.profile.tesseract_chars.label())
+                        .show_ui(ui, |ui| {
+                            for preset in config::CharPreset::ALL {
+                                save_chars |= ui
+                                    .selectable_value(
+                                        &mut self.profile.tesseract_chars,
+                                        preset,
+                                        preset.label(),
+                                    )
+                                    .changed();
+                            }
+                        });
+                    if self.profile.tesseract_chars == config::CharPreset::Custom {
+                        egui::Grid::new("chars_custom")
+                            .num_columns(2)
+                            .show(ui, |ui| {
+                                ui.label("Whitelist");
+                                let response =
+                                    ui.text_edit_singleline(&mut self.profile.tesseract_whitelist);
+                                chars_changed |= response.changed();
+                                save_chars |= response.lost_focus();
+                                ui.end_row();
+                                ui.label("Blacklist");
+                                let response =
+                                    ui.text_edit_singleline(&mut self.profile.tesseract_blacklist);
+                                chars_changed |= response.changed();
+                                save_chars |= response.lost_focus();
+                                ui.end_row();
+                                ui.label("User patterns file");
+                                let mut patterns = self
+                                    .profile
+                                    .tesseract_user_patterns
+                                    .as_ref()
+                                    .map(|p| p.display().to_string())
+                                    .unwrap_or_default();
+                                let response = ui.text_edit_singleline(&mut patterns);
+                                if response.changed() {
+                                    self.profile.tesseract_user_patterns = (!patterns.is_empty())
+                                        .then(|| std::path::PathBuf::from(patterns));
+                                    chars_changed = true;
+                                }
+                                save_chars |= response.lost_focus();
+                                ui.end_row();
+                            });
+                    }
+                    if chars_changed || save_chars {
+                        Self::apply_char_preset(&mut self.tesseract_args, &self.profile);
+                    }
+                    if save_chars {
+                        self.save_profile();
+                    }
+
                     let mut dpi_float = self.tesseract_args.dpi.unwrap_or(150) as f32;
                     if ui
                         .add(egui::Slider::new(&mut dpi_float, 50.0..=300.0).suffix("dpi"))
@@ -276,22 +887,109 @@ impl ScreenshotApp {
                     self.start_image_recognition_with_gemini();
                 }
 
-                if !self.results.is_empty() {
-                    if ui.button("Copy Text").clicked() {
-                        if let Err(e) = copy_text_with_wl_copy(&self.results) {
-                            eprintln!("Error al copiar al portapapeles: {}", e);
-                            self.results = format!("Error al copiar: {}", e);
+                #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+                {
+                    let mut text_mode = self.profile.text_mode;
+                    egui::ComboBox::from_label("Text mode")
+                        .selected_text(text_mode.label())
+                        .show_ui(ui, |ui| {
+                            for mode in config::TextMode::ALL {
+                                ui.selectable_value(&mut text_mode, mode, mode.label());
+                            }
+                        });
+                    if text_mode != self.profile.text_mode {
+                        self.profile.text_mode = text_mode;
+                        self.save_profile();
+                        if !self.ocr_lines.is_empty() {
+                            self.update_results();
+                            self.write_exports();
                         }
                     }
+
+                    let response = ui.add(
+                        egui::Slider::new(&mut self.profile.min_confidence, 0.0..=100.0)
+                            .text("Min confidence"),
+                    );
+                    if response.changed() {
+                        self.filter_ocr_lines();
+                    }
+                    // Se guarda y se exporta al soltar el control, no en cada paso del arrastre.
+                    if response.drag_stopped() || (response.changed() && !response.dragged()) {
+                        self.save_profile();
+                        self.write_exports();
+                    }
+                }
+
+                #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+                if let Some(table) = self.table.as_ref().filter(|_| !self.ocr_lines.is_empty()) {
+                    let mut copied = None;
+                    ui.horizontal(|ui| {
+                        ui.label("Copy table as:");
+                        if ui.button("CSV").clicked() {
+                            copied = Some(clipboard::Content::Text(table.to_csv()));
+                        }
+                        if ui.button("TSV").clicked() {
+                            copied = Some(clipboard::Content::Text(table.to_tsv()));
+                        }
+                        if ui.button("Markdown").clicked() {
+                            copied = Some(clipboard::Content::Text(table.to_markdown()));
+                        }
+                        if ui
+                            .button("HTML")
+                            .on_hover_text("For spreadsheets and rich text editors")
+                            .clicked()
+                        {
+                            copied = Some(clipboard::Content::Html {
+                                html: table.to_html(),
+                                alt_text: table.to_tsv(),
+                            });
+                        }
+                    });
+                    if let Some(content) = copied {
+                        self.copy(content, clipboard::Selection::Clipboard);
+                    }
                 }
 
+                #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+                if !self.ocr_lines.is_empty() {
+                    ui.menu_button("Export", |ui| {
+                        for format in export::Format::ALL {
+                            if ui.button(format.label()).clicked() {
+                                self.export(format);
+                                ui.close_menu();
+                            }
+                        }
+                        ui.separator();
+                        if ui.button("Copy JSON").clicked() {
+                            if let Some(json) = self.render_export(export::Format::Json) {
+                                self.copy_text(&String::from_utf8_lossy(&json));
+                            }
+                            ui.close_menu();
+                        }
+                    });
+                }
+
+                if !self.results.is_empty() && ui.button("Copy Text").clicked() {
+                    self.copy_text(&self.results.clone());
+                }
+
+                let (low_confidence, generated) = (&self.low_confidence, &self.generated_results);
+                let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+                    let ranges: &[Range<usize>] = if text == generated.as_str() {
+                        low_confidence
+                    } else {
+                        &[]
+                    };
+                    highlight_ranges(ui, text, wrap_width, ranges)
+                };
                 ui.add(
                     egui::TextEdit::multiline(&mut self.results)
                         .font(egui::TextStyle::Monospace)
                         .code_editor()
                         .desired_rows(10)
                         .lock_focus(true)
-                        .desired_width(300.0),
+                        .desired_width(300.0)
+                        .layouter(&mut layouter),
                 );
             });
 
@@ -305,40 +1003,17 @@ impl ScreenshotApp {
         if self.is_ai_working {
             return;
         }
-        if let Some(selection_rect) = self.selection {
-            let sel = selection_rect.normalized();
-            let x = sel.min.x.round() as u32;
-            let y = sel.min.y.round() as u32;
-            let width = sel.width().round() as u32;
-            let height = sel.height().round() as u32;
-
-            if width == 0 || height == 0 {
-                return;
-            }
-
-            let cropped_rgba =
-                image::imageops::crop_imm(&self.screenshot_image, x, y, width, height).to_image();
-            let mut image_bytes: Vec<u8> = Vec::new();
-            let encoder = image::codecs::png::PngEncoder::new(&mut image_bytes);
-            if encoder
-                .write_image(
-                    &cropped_rgba,
-                    cropped_rgba.width(),
-                    cropped_rgba.height(),
-                    image::ColorType::Rgba8.into(),
-                )
-                .is_err()
-            {
-                self.results = "Error: No se pudo codificar la imagen a PNG.".to_string();
-                return;
-            }
-
+        self.last_engine = Some(config::Engine::Paddleocr);
+        self.recognition_failed = false;
+        let started = Instant::now();
+        if let Some((image_bytes, transform)) = self.selection_png() {
+            let preprocessed = Instant::now();
             let (sender, receiver) = mpsc::channel();
-            self.ocr_results.clear();
+            self.clear_ocr();
             self.results.clear();
             self.is_ai_working = false;
 
-            let paddle_clone = self.paddle_client.clone();
+            let paddle_clone = self.paddle.clone();
             let owned_image_bytes = image_bytes;
             let owned_sender = sender;
 
@@ -348,33 +1023,47 @@ impl ScreenshotApp {
                     .await;
             });
 
-            let results = receiver.recv().unwrap();
+            let results = match receiver.recv() {
+                Ok(Ok(results)) => results,
+                Ok(Err(e)) => {
+                    self.results = e;
+                    self.recognition_failed = true;
+                    return;
+                }
+                Err(_) => {
+                    eprintln!("[PaddleOCR] El motor terminó sin devolver resultados.");
+                    self.results = "El motor terminó sin devolver resultados.".to_string();
+                    self.recognition_failed = true;
+                    return;
+                }
+            };
+            #[cfg(feature = "paddle_onnx")]
+            let model = format!("onnx ({})", self.config.paddle_model_dir().display());
+            #[cfg(not(feature = "paddle_onnx"))]
+            let model = "server".to_string();
+            self.recognition = export::Metadata {
+                engine: "paddleocr",
+                model,
+                language: None,
+                preprocess_ms: (preprocessed - started).as_millis() as u64,
+                recognition_ms: preprocessed.elapsed().as_millis() as u64,
+            };
             for result in &results {
                 let points: Vec<egui::Pos2> = result
                     .coordinates
                     .iter()
-                    .map(|p| egui::pos2(p[0] * 2.0, p[1] * 2.0))
+                    .map(|p| transform.source_point(egui::pos2(p[0], p[1])))
                     .collect();
                 let bounding_box = egui::Rect::from_points(&points);
                 self.ocr_results.push(OcrWord {
                     text: result.text.clone(),
                     bbox: bounding_box,
+                    confidence: result.score.map(|score| score * 100.0),
+                    polygon: points.try_into().ok(),
                 })
             }
-            self.ocr_lines = Self::group_words_into_lines(&self.ocr_results);
-            self.results = self
-                .ocr_lines
-                .iter()
-                .map(|line| {
-                    line.words
-                        .iter()
-                        .map(|w| w.text.trim().to_string())
-                        .collect::<Vec<_>>()
-                        .join(" ")
-                })
-                .collect::<Vec<_>>()
-                .join(" ");
-            println!("{}", self.results);
+            self.set_ocr_lines(Self::group_words_into_lines(&self.ocr_results), false);
+            report!("{}", self.results);
         }
     }
 
@@ -383,34 +1072,9 @@ impl ScreenshotApp {
         if self.is_ai_working {
             return;
         }
-        if let Some(selection_rect) = self.selection {
-            let sel = selection_rect.normalized();
-            let x = sel.min.x.round() as u32;
-            let y = sel.min.y.round() as u32;
-            let width = sel.width().round() as u32;
-            let height = sel.height().round() as u32;
-
-            if width == 0 || height == 0 {
-                return;
-            }
-
-            let cropped_rgba =
-                image::imageops::crop_imm(&self.screenshot_image, x, y, width, height).to_image();
-            let mut image_bytes: Vec<u8> = Vec::new();
-            let encoder = image::codecs::png::PngEncoder::new(&mut image_bytes);
-            if encoder
-                .write_image(
-                    &cropped_rgba,
-                    cropped_rgba.width(),
-                    cropped_rgba.height(),
-                    image::ColorType::Rgba8.into(),
-                )
-                .is_err()
-            {
-                self.results = "Error: No se pudo codificar la imagen a PNG.".to_string();
-                return;
-            }
-
+        self.last_engine = Some(config::Engine::Ollama);
+        self.recognition_failed = false;
+        if let Some((image_bytes, _)) = self.selection_png() {
             let (sender, receiver) = mpsc::channel();
             self.ai_result_receiver = Some(receiver);
             self.is_ai_working = true;
@@ -432,34 +1096,9 @@ impl ScreenshotApp {
         if self.is_ai_working {
             return;
         }
-        if let Some(selection_rect) = self.selection {
-            let sel = selection_rect.normalized();
-            let x = sel.min.x.round() as u32;
-            let y = sel.min.y.round() as u32;
-            let width = sel.width().round() as u32;
-            let height = sel.height().round() as u32;
-
-            if width == 0 || height == 0 {
-                return;
-            }
-
-            let cropped_rgba =
-                image::imageops::crop_imm(&self.screenshot_image, x, y, width, height).to_image();
-            let mut image_bytes: Vec<u8> = Vec::new();
-            let encoder = image::codecs::png::PngEncoder::new(&mut image_bytes);
-            if encoder
-                .write_image(
-                    &cropped_rgba,
-                    cropped_rgba.width(),
-                    cropped_rgba.height(),
-                    image::ColorType::Rgba8.into(),
-                )
-                .is_err()
-            {
-                self.results = "Error: No se pudo codificar la imagen a PNG.".to_string();
-                return;
-            }
-
+        self.last_engine = Some(config::Engine::Gemini);
+        self.recognition_failed = false;
+        if let Some((image_bytes, _)) = self.selection_png() {
             let (sender, receiver) = mpsc::channel();
             self.ai_result_receiver = Some(receiver);
             self.is_ai_working = true;
@@ -482,194 +1121,677 @@ impl ScreenshotApp {
                 if self.results == "Analizando imagen con IA..." {
                     self.results.clear();
                 }
-                self.results.push_str(&chunk);
+                match chunk {
+                    Ok(text) => self.results.push_str(&text),
+                    Err(e) => {
+                        self.results.push_str(&e);
+                        self.recognition_failed = true;
+                    }
+                }
             }
             if let Err(TryRecvError::Disconnected) = receiver.try_recv() {
                 self.is_ai_working = false;
                 self.ai_result_receiver = None;
+                self.notify_result();
             }
         }
     }
 
-    #[cfg(feature = "tesseract")]
-    fn preprocess_image_for_ocr(image: &DynamicImage) -> DynamicImage {
-        let gray = image.to_luma8();
+    fn run_default_engine(&mut self) {
+        self.run_engine(self.profile.engine.resolve());
+    }
 
-        let mut binary = gray.clone();
-        image::imageops::contrast(&mut binary, 1.5);
+    /// El último motor usado sobre la selección o, si aún no se ha usado ninguno, el
+    /// predeterminado.
+    fn active_engine(&self) -> Option<config::Engine> {
+        self.last_engine.or_else(|| self.profile.engine.resolve())
+    }
 
-        let mut denoised = image::imageops::blur(&binary, 1.0);
+    fn run_engine(&mut self, engine: Option<config::Engine>) {
+        match engine {
+            #[cfg(feature = "tesseract")]
+            Some(config::Engine::Tesseract) => self.perform_ocr(),
+            #[cfg(feature = "paddleocr")]
+            Some(config::Engine::Paddleocr) => self.start_recognition_with_paddle(),
+            #[cfg(feature = "ollama")]
+            Some(config::Engine::Ollama) => self.start_image_recognition_with_ai(),
+            #[cfg(feature = "gemini")]
+            Some(config::Engine::Gemini) => self.start_image_recognition_with_gemini(),
+            _ => eprintln!("No hay ningún motor de reconocimiento compilado."),
+        }
+    }
+
+    /// Copia el resultado de `--autocopy` y cierra la ventana en cuanto termina el motor.
+    fn finish_autocopy(&mut self, ctx: &egui::Context) {
+        #[cfg(any(feature = "ollama", feature = "gemini"))]
+        if self.is_ai_working {
+            return;
+        }
+        self.autocopy_pending = false;
+
+        let text = self.results.trim().to_string();
+        if self.recognition_failed {
+            eprintln!("El reconocimiento falló; no se copia nada.");
+        } else if text.is_empty() {
+            eprintln!("No se reconoció texto en la selección.");
+        } else {
+            self.copy_text(&text);
+            if self.print_result {
+                println!("{}", text);
+            }
+        }
+        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+    }
+
+    /// Con `--notify`, muestra el texto recién reconocido en una notificación; los errores no
+    /// se notifican como texto.
+    fn notify_result(&self) {
+        let text = self.results.trim();
+        if !self.profile.notify || self.recognition_failed || text.is_empty() {
+            return;
+        }
+        if let Err(e) = notify::show(text) {
+            eprintln!("[notificación] No se pudo mostrar la notificación: {}", e);
+        }
+    }
+
+    /// Píxeles de la captura por cada punto de egui. Incluye tanto `pixels_per_point` como la
+    /// escala de la salida, porque la captura se estira sobre `image_rect`.
+    fn pixels_per_point(&self) -> egui::Vec2 {
+        egui::vec2(
+            self.screenshot_image.width() as f32 / self.image_rect.width(),
+            self.screenshot_image.height() as f32 / self.image_rect.height(),
+        )
+    }
+
+    fn clear_ocr(&mut self) {
+        self.ocr_results.clear();
+        self.ocr_lines.clear();
+        #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+        self.raw_lines.clear();
+        self.text_selection = None;
+    }
+
+    /// Índice (en `ocr_results`) de la palabra bajo `pos`, en coordenadas de pantalla.
+    fn word_at(&self, pos: egui::Pos2) -> Option<usize> {
+        self.ocr_results.iter().position(|word| match word.polygon {
+            Some(polygon) => self
+                .crop_quad_to_screen(polygon)
+                .is_some_and(|q| quad::contains(&q, pos)),
+            None => self
+                .crop_rect_to_screen(word.bbox)
+                .is_some_and(|rect| rect.expand(2.0).contains(pos)),
+        })
+    }
+
+    /// Primer y último índice de la línea que contiene la palabra `word`.
+    fn line_range(&self, word: usize) -> (usize, usize) {
+        let mut start = 0;
+        for line in &self.ocr_lines {
+            let end = start + line.words.len();
+            if (start..end).contains(&word) {
+                return (start, end - 1);
+            }
+            start = end;
+        }
+        (word, word)
+    }
+
+    /// Texto de las palabras entre `start` y `end` (inclusive), con un salto por cada línea.
+    fn words_text(&self, start: usize, end: usize) -> String {
+        let range = start.min(end)..=start.max(end);
+        let mut index = 0;
+        let mut lines = Vec::new();
+        for line in &self.ocr_lines {
+            let words: Vec<&str> = line
+                .words
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| range.contains(&(index + i)))
+                .map(|(_, w)| w.text.trim())
+                .collect();
+            index += line.words.len();
+            if !words.is_empty() {
+                lines.push(words.join(" "));
+            }
+        }
+        lines.join("\n")
+    }
+
+    fn copy(&mut self, content: clipboard::Content, selection: clipboard::Selection) {
+        if let Err(e) = clipboard::copy(content, selection) {
+            eprintln!("Error al copiar al portapapeles: {}", e);
+            self.results = format!("Error al copiar: {}", e);
+        }
+    }
 
-        for pixel in denoised.pixels_mut() {
-            if pixel.0[0] > 128 {
-                pixel.0[0] = 255;
+    fn copy_text(&mut self, text: &str) {
+        self.copy(
+            clipboard::Content::Text(text.to_string()),
+            clipboard::Selection::Clipboard,
+        );
+    }
+
+    /// Resalta las palabras seleccionadas y muestra el texto de la palabra bajo el cursor.
+    fn draw_text_layer(
+        &self,
+        ctx: &egui::Context,
+        painter: &egui::Painter,
+        hover_pos: Option<egui::Pos2>,
+    ) {
+        let selected = self
+            .text_selection
+            .map(|(start, end)| start.min(end)..=start.max(end));
+        let hovered = hover_pos.and_then(|pos| self.word_at(pos));
+
+        for (i, word) in self.ocr_results.iter().enumerate() {
+            let fill = if selected.as_ref().is_some_and(|range| range.contains(&i)) {
+                egui::Color32::from_rgba_unmultiplied(80, 140, 255, 90)
             } else {
-                pixel.0[0] = 0;
+                egui::Color32::TRANSPARENT
+            };
+            let color = word
+                .confidence
+                .map_or(egui::Color32::LIGHT_GRAY, confidence_color);
+            let stroke = egui::Stroke::new(1.0, color);
+            if let Some(shape) = self.screen_shape(word.bbox, word.polygon, 0.0, 2.0, fill, stroke)
+            {
+                painter.add(shape);
+            }
+            if hovered == Some(i)
+                && let Some(shape) = self.screen_shape(
+                    word.bbox,
+                    word.polygon,
+                    1.0,
+                    2.0,
+                    egui::Color32::TRANSPARENT,
+                    egui::Stroke::new(1.0, egui::Color32::LIGHT_BLUE),
+                )
+            {
+                painter.add(shape);
             }
         }
 
-        let scaled = image::DynamicImage::ImageLuma8(denoised).resize(
-            image.width() * 2,
-            image.height() * 2,
-            FilterType::Lanczos3,
+        if let Some(i) = hovered
+            && self.drag_mode == DragMode::None
+        {
+            ctx.set_cursor_icon(egui::CursorIcon::Text);
+            egui::show_tooltip_at_pointer(ctx, egui::Id::new("ocr_word_tooltip"), |ui| {
+                let word = &self.ocr_results[i];
+                match word.confidence {
+                    Some(confidence) => {
+                        ui.label(format!("{} ({:.0}%)", word.text.trim(), confidence))
+                    }
+                    None => ui.label(word.text.trim()),
+                };
+            });
+        }
+    }
+
+    /// Selección convertida a píxeles de `screenshot_image`, recortada a sus límites.
+    fn selection_pixels(&self) -> Option<egui::Rect> {
+        let sel = self.selection?.normalized();
+        let scale = self.pixels_per_point();
+        let bounds = egui::Rect::from_min_size(
+            egui::Pos2::ZERO,
+            egui::vec2(
+                self.screenshot_image.width() as f32,
+                self.screenshot_image.height() as f32,
+            ),
         );
+        let to_pixels = |p: egui::Pos2| ((p - self.image_rect.min) * scale).round().to_pos2();
+        Some(egui::Rect::from_min_max(to_pixels(sel.min), to_pixels(sel.max)).intersect(bounds))
+    }
 
-        scaled
+    /// Pasa un punto relativo al recorte (en píxeles) a coordenadas de pantalla.
+    fn crop_point_to_screen(&self, point: egui::Pos2) -> Option<egui::Pos2> {
+        let origin = self.selection_pixels()?.min.to_vec2();
+        Some(self.image_rect.min + (point.to_vec2() + origin) / self.pixels_per_point())
     }
 
-    #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
-    fn group_words_into_lines(words: &[OcrWord]) -> Vec<OcrLine> {
+    fn crop_rect_to_screen(&self, rect: egui::Rect) -> Option<egui::Rect> {
+        Some(egui::Rect::from_min_max(
+            self.crop_point_to_screen(rect.min)?,
+            self.crop_point_to_screen(rect.max)?,
+        ))
+    }
+
+    fn crop_quad_to_screen(&self, quad: quad::Quad) -> Option<quad::Quad> {
+        let mut screen = quad;
+        for p in &mut screen {
+            *p = self.crop_point_to_screen(*p)?;
+        }
+        Some(screen)
+    }
+
+    /// Forma en pantalla de una caja del recorte: su polígono si lo tiene, o el rectángulo
+    /// ampliado `expand` puntos.
+    fn screen_shape(
+        &self,
+        rect: egui::Rect,
+        polygon: Option<quad::Quad>,
+        expand: f32,
+        rounding: f32,
+        fill: egui::Color32,
+        stroke: egui::Stroke,
+    ) -> Option<egui::Shape> {
+        Some(match polygon {
+            Some(polygon) => egui::Shape::convex_polygon(
+                self.crop_quad_to_screen(polygon)?.to_vec(),
+                fill,
+                stroke,
+            ),
+            None => egui::Shape::Rect(egui::epaint::RectShape::new(
+                self.crop_rect_to_screen(rect)?.expand(expand),
+                rounding,
+                fill,
+                stroke,
+            )),
+        })
+    }
+
+    fn selection_image(&self) -> Option<DynamicImage> {
+        let sel = self.selection_pixels()?;
+        let x = sel.min.x as u32;
+        let y = sel.min.y as u32;
+        let width = sel.width().max(0.0) as u32;
+        let height = sel.height().max(0.0) as u32;
+
+        if width == 0 || height == 0 {
+            return None;
+        }
+
+        Some(DynamicImage::ImageRgba8(
+            image::imageops::crop_imm(&self.screenshot_image, x, y, width, height).to_image(),
+        ))
+    }
+
+    /// Recorte codificado en PNG para los motores externos, preprocesado si así se ha configurado.
+    #[cfg(any(feature = "paddleocr", feature = "ollama", feature = "gemini"))]
+    fn selection_png(&mut self) -> Option<(Vec<u8>, preprocess::Transform)> {
+        let cropped = self.selection_image()?;
+        let (image, transform) = self.profile.preprocess.for_engine(&cropped, false);
+
+        let image_bytes = self.encode_png(&image)?;
+        Some((image_bytes, transform))
+    }
+
+    fn encode_png(&mut self, image: &DynamicImage) -> Option<Vec<u8>> {
+        let mut image_bytes: Vec<u8> = Vec::new();
+        let encoder = image::codecs::png::PngEncoder::new(&mut image_bytes);
+        if image.write_with_encoder(encoder).is_err() {
+            self.results = "Error: No se pudo codificar la imagen a PNG.".to_string();
+            return None;
+        }
+        Some(image_bytes)
+    }
+
+    /// Ruta propuesta para guardar el recorte, según la plantilla de la configuración.
+    fn default_image_path(&self) -> String {
+        let size = self
+            .selection_pixels()
+            .map(|r| r.size())
+            .unwrap_or_default();
+        self.config
+            .output_path(
+                "png",
+                &[
+                    ("profile", self.profile_name.clone()),
+                    ("width", size.x.to_string()),
+                    ("height", size.y.to_string()),
+                ],
+            )
+            .display()
+            .to_string()
+    }
+
+    /// Guarda el recorte tal cual, sin preprocesar.
+    fn save_image(&mut self, path: &std::path::Path) {
+        let Some(image) = self.selection_image() else {
+            return;
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| image.save(path).map_err(io::Error::other));
+        match result {
+            Ok(()) => report!("Imagen guardada en '{}'.", path.display()),
+            Err(e) => {
+                eprintln!("Error al guardar '{}': {}", path.display(), e);
+                self.results = format!("Error al guardar la imagen: {}", e);
+            }
+        }
+    }
+
+    fn copy_image(&mut self) {
+        let Some(image) = self.selection_image() else {
+            return;
+        };
+        let Some(png) = self.encode_png(&image) else {
+            return;
+        };
+        self.copy(
+            clipboard::Content::Png(png),
+            clipboard::Selection::Clipboard,
+        );
+    }
+
+    /// Vista previa de lo que recibe el motor activo. Tras un cambio de los ajustes se
+    /// sigue mostrando la anterior hasta que pasa `PREVIEW_DELAY` sin más cambios.
+    fn preprocess_preview(&mut self, ctx: &egui::Context) -> Option<egui::TextureHandle> {
+        if let Some(changed) = self.preview_changed {
+            let elapsed = changed.elapsed();
+            if elapsed < PREVIEW_DELAY {
+                ctx.request_repaint_after(PREVIEW_DELAY - elapsed);
+            } else {
+                self.preview_changed = None;
+                self.preview_texture = None;
+            }
+        }
+        if self.preview_texture.is_none() {
+            let tesseract = self.active_engine() == Some(config::Engine::Tesseract);
+            let (image, _) = self
+                .profile
+                .preprocess
+                .for_engine(&self.selection_image()?, tesseract);
+            let rgba = image.to_rgba8();
+            let color_image = egui::ColorImage::from_rgba_unmultiplied(
+                [rgba.width() as usize, rgba.height() as usize],
+                &rgba,
+            );
+            self.preview_texture = Some(ctx.load_texture(
+                "preprocess-preview",
+                color_image,
+                egui::TextureOptions::LINEAR,
+            ));
+        }
+        self.preview_texture.clone()
+    }
+
+    #[cfg(feature = "tesseract")]
+    fn lines_from_tesseract(words: Vec<Data>, transform: preprocess::Transform) -> Vec<OcrLine> {
         let mut lines: Vec<OcrLine> = Vec::new();
+        let mut current_line = None;
+        let mut paragraph = 0;
+
+        for word in words {
+            if word.text.trim().is_empty() {
+                continue;
+            }
+
+            let line_key = (word.block_num, word.par_num, word.line_num);
+            if current_line != Some(line_key) {
+                if let Some((block, par, _)) = current_line
+                    && (block, par) != (word.block_num, word.par_num)
+                {
+                    paragraph += 1;
+                }
+                current_line = Some(line_key);
+                lines.push(OcrLine {
+                    words: Vec::new(),
+                    bbox: egui::Rect::NOTHING,
+                    paragraph,
+                });
+            }
+
+            let line = lines.last_mut().expect("línea recién creada");
+            line.words.push(OcrWord {
+                confidence: Some(word.conf),
+                polygon: None,
+                text: word.text,
+                bbox: transform.source_rect(egui::Rect::from_min_size(
+                    egui::pos2(word.left as f32, word.top as f32),
+                    egui::vec2(word.width as f32, word.height as f32),
+                )),
+            });
+        }
+
+        for line in &mut lines {
+            line.bbox = line
+                .words
+                .iter()
+                .fold(egui::Rect::NOTHING, |acc, w| acc.union(w.bbox));
+        }
+
+        lines
+    }
+
+    /// Agrupa las detecciones en líneas según su orientación, así el texto inclinado no se
+    /// mezcla con las líneas vecinas.
+    #[cfg(feature = "paddleocr")]
+    fn group_words_into_lines(words: &[OcrWord]) -> Vec<OcrLine> {
         let mut sorted_words = words.to_vec();
         sorted_words.sort_by(|a, b| {
             a.bbox
                 .min
                 .y
-                .partial_cmp(&b.bbox.min.y)
-                .unwrap_or(std::cmp::Ordering::Equal)
-                .then(a.bbox.min.x.partial_cmp(&b.bbox.min.x).unwrap())
+                .total_cmp(&b.bbox.min.y)
+                .then(a.bbox.min.x.total_cmp(&b.bbox.min.x))
         });
 
-        let mut current_line: Vec<OcrWord> = Vec::new();
+        let mut groups: Vec<(Vec<quad::Quad>, Vec<OcrWord>)> = Vec::new();
         for word in sorted_words {
-            if current_line.is_empty() {
-                current_line.push(word);
-                continue;
+            let word_quad = word.quad();
+            match groups
+                .iter_mut()
+                .find(|(quads, _)| quad::continues_line(quads, &word_quad))
+            {
+                Some((quads, line)) => {
+                    quads.push(word_quad);
+                    line.push(word);
+                }
+                None => groups.push((vec![word_quad], vec![word])),
             }
+        }
 
-            let last_word = current_line.last().unwrap();
-            let y_diff = (word.bbox.min.y - last_word.bbox.max.y).abs();
-            let x_diff = word.bbox.min.x - last_word.bbox.max.x;
+        groups
+            .into_iter()
+            .map(|(quads, mut words)| {
+                let direction = quad::direction(&quads[0]);
+                words.sort_by(|a, b| {
+                    quad::position_along(&a.quad(), direction)
+                        .total_cmp(&quad::position_along(&b.quad(), direction))
+                });
+                OcrLine {
+                    bbox: words
+                        .iter()
+                        .fold(egui::Rect::NOTHING, |acc, w| acc.union(w.bbox)),
+                    words,
+                    paragraph: 0,
+                }
+            })
+            .collect()
+    }
 
-            if y_diff < 50.0 && x_diff > -50.0 && x_diff < 50.0 {
-                current_line.push(word);
-            } else {
-                let min_x = current_line
-                    .iter()
-                    .map(|w| w.bbox.min.x)
-                    .fold(f32::INFINITY, f32::min);
-                let min_y = current_line
-                    .iter()
-                    .map(|w| w.bbox.min.y)
-                    .fold(f32::INFINITY, f32::min);
-                let max_x = current_line
-                    .iter()
-                    .map(|w| w.bbox.max.x)
-                    .fold(f32::NEG_INFINITY, f32::max);
-                let max_y = current_line
+    #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+    fn set_ocr_lines(&mut self, lines: Vec<OcrLine>, engine_paragraphs: bool) {
+        self.raw_lines = lines;
+        self.engine_paragraphs = engine_paragraphs;
+        self.filter_ocr_lines();
+        self.write_exports();
+        self.notify_result();
+    }
+
+    /// Descarta las palabras por debajo de la confianza mínima del perfil, ordena las líneas
+    /// para la lectura y regenera el texto y la tabla. No vuelve a ejecutar el OCR.
+    #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+    fn filter_ocr_lines(&mut self) {
+        if self.raw_lines.is_empty() {
+            return;
+        }
+        let min_confidence = self.profile.min_confidence;
+        let lines = self
+            .raw_lines
+            .iter()
+            .filter_map(|line| {
+                let words: Vec<OcrWord> = line
+                    .words
                     .iter()
-                    .map(|w| w.bbox.max.y)
-                    .fold(f32::NEG_INFINITY, f32::max);
-                let line_bbox = egui::Rect::from_min_max(
-                    egui::pos2(min_x / 2.0, min_y / 2.0),
-                    egui::pos2(max_x / 2.0, max_y / 2.0),
-                );
+                    // Sin confianza conocida no hay motivo para descartar la palabra.
+                    .filter(|w| w.confidence.is_none_or(|c| c >= min_confidence))
+                    .cloned()
+                    .collect();
+                (!words.is_empty()).then(|| OcrLine {
+                    bbox: words
+                        .iter()
+                        .fold(egui::Rect::NOTHING, |acc, w| acc.union(w.bbox)),
+                    words,
+                    paragraph: line.paragraph,
+                })
+            })
+            .collect();
+        self.text_selection = None;
+        self.ocr_lines = layout::reading_order(lines, self.engine_paragraphs);
+        self.ocr_results = self
+            .ocr_lines
+            .iter()
+            .flat_map(|line| line.words.iter().cloned())
+            .collect();
+        self.table = table::Table::detect(&self.ocr_lines);
+        self.update_results();
+    }
 
-                lines.push(OcrLine {
-                    words: current_line,
-                    bbox: line_bbox,
-                });
-                current_line = vec![word];
-            }
+    #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+    fn write_exports(&self) {
+        if self.raw_lines.is_empty() {
+            return;
         }
+        for (format, path) in &self.exports {
+            self.write_export(*format, path);
+        }
+    }
 
-        if !current_line.is_empty() {
-            let min_x = current_line
-                .iter()
-                .map(|w| w.bbox.min.x)
-                .fold(f32::INFINITY, f32::min);
-            let min_y = current_line
-                .iter()
-                .map(|w| w.bbox.min.y)
-                .fold(f32::INFINITY, f32::min);
-            let max_x = current_line
-                .iter()
-                .map(|w| w.bbox.max.x)
-                .fold(f32::NEG_INFINITY, f32::max);
-            let max_y = current_line
-                .iter()
-                .map(|w| w.bbox.max.y)
-                .fold(f32::NEG_INFINITY, f32::max);
-            let line_bbox = egui::Rect::from_min_max(
-                egui::pos2(min_x / 2.0, min_y / 2.0),
-                egui::pos2(max_x / 2.0, max_y / 2.0),
+    /// Regenera `results` a partir de `ocr_lines` con el modo de texto del perfil.
+    #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+    fn update_results(&mut self) {
+        let text = layout::to_text(&self.ocr_lines, self.profile.text_mode);
+        let words = self.ocr_lines.iter().flat_map(|line| &line.words);
+        self.low_confidence = text
+            .word_spans
+            .into_iter()
+            .zip(words)
+            .filter(|(_, word)| word.confidence.is_some_and(|c| c < LOW_CONFIDENCE))
+            .map(|(span, _)| span)
+            .collect();
+        self.low_confidence.sort_by_key(|span| span.start);
+        self.results = text.text;
+        self.generated_results = self.results.clone();
+    }
+
+    /// Guarda el resultado con el nombre de `file_template` en la carpeta de exportación.
+    #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+    fn export(&self, format: export::Format) {
+        let size = self
+            .selection_pixels()
+            .map(|r| r.size())
+            .unwrap_or_default();
+        let path = self.config.output_path(
+            format.extension(),
+            &[
+                ("profile", self.profile_name.clone()),
+                ("width", size.x.to_string()),
+                ("height", size.y.to_string()),
+            ],
+        );
+        self.write_export(format, &path);
+    }
+
+    #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+    fn render_export(&self, format: export::Format) -> Option<Vec<u8>> {
+        let image = self.selection_image()?;
+        let selection = self.selection_pixels()?;
+        // Las coordenadas de pantalla son las de la salida en la que empieza la selección.
+        let (screen_origin, screen_scale) = self
+            .monitors
+            .iter()
+            .find(|m| m.rect.contains(selection.min))
+            .map_or(
+                (
+                    self.capture_origin + selection.min.to_vec2() / self.capture_scale,
+                    self.capture_scale,
+                ),
+                |m| (m.screen_point(selection.min), m.pixels_per_point()),
             );
+        Some(format.render(&export::Document {
+            lines: &self.ocr_lines,
+            image: &image,
+            text: &self.results,
+            metadata: &self.recognition,
+            screen_origin,
+            screen_scale,
+        }))
+    }
 
-            lines.push(OcrLine {
-                words: current_line,
-                bbox: line_bbox,
-            });
+    #[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+    fn write_export(&self, format: export::Format, path: &std::path::Path) {
+        let Some(contents) = self.render_export(format) else {
+            return;
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(path, contents));
+        match result {
+            Ok(()) => report!("Resultado exportado a '{}'.", path.display()),
+            Err(e) => eprintln!(
+                "[export] Error al escribir '{}' ({}): {}",
+                path.display(),
+                format.label(),
+                e
+            ),
         }
+    }
 
-        lines
+    #[cfg(all(feature = "tesseract", not(feature = "tesseract_api")))]
+    fn run_tesseract(
+        &mut self,
+        image: &DynamicImage,
+    ) -> Result<Vec<Data>, Box<dyn std::error::Error>> {
+        let tesseract_image = TessImage::from_dynamic_image(image)?;
+        Ok(rusty_tesseract::image_to_data(&tesseract_image, &self.tesseract_args)?.data)
+    }
+
+    #[cfg(feature = "tesseract_api")]
+    fn run_tesseract(
+        &mut self,
+        image: &DynamicImage,
+    ) -> Result<Vec<Data>, Box<dyn std::error::Error>> {
+        self.tesseract_api
+            .recognize(&image.to_luma8(), &self.tesseract_args)
     }
 
     #[cfg(feature = "tesseract")]
     fn perform_ocr(&mut self) {
-        if let Some(selection_rect) = self.selection {
-            let sel = selection_rect.normalized();
-            let x = sel.min.x.round() as u32;
-            let y = sel.min.y.round() as u32;
-            let width = sel.width().round() as u32;
-            let height = sel.height().round() as u32;
-
-            if width > 0 && height > 0 {
-                let cropped_dyn_image = DynamicImage::ImageRgba8(
-                    image::imageops::crop_imm(&self.screenshot_image, x, y, width, height)
-                        .to_image(),
-                );
-
-                let preprocessed_image = Self::preprocess_image_for_ocr(&cropped_dyn_image);
-
-                let tesseract_image = TessImage::from_dynamic_image(&preprocessed_image)
-                    .expect("No se pudo crear la imagen para Tesseract");
-
-                println!("Ejecutando OCR en la selección...");
-                match rusty_tesseract::image_to_data(&tesseract_image, &self.tesseract_args) {
-                    Ok(data) => {
-                        self.ocr_results.clear();
-                        self.results.clear();
-                        for line in data.output.lines().skip(1) {
-                            let columns: Vec<&str> = line.split('\t').collect();
-                            if columns.len() == 12 {
-                                if let (Ok(confidence), Ok(x), Ok(y), Ok(w), Ok(h)) = (
-                                    columns[10].parse::<f32>(),
-                                    columns[6].parse::<f32>(),
-                                    columns[7].parse::<f32>(),
-                                    columns[8].parse::<f32>(),
-                                    columns[9].parse::<f32>(),
-                                ) {
-                                    let text = columns[11];
-                                    if confidence > 10.0 && !text.trim().is_empty() {
-                                        self.results.push_str(&format!("{} ", text.trim()));
-                                        self.ocr_results.push(OcrWord {
-                                            text: text.to_string(),
-                                            bbox: egui::Rect::from_min_size(
-                                                egui::pos2(x, y),
-                                                egui::vec2(w, h),
-                                            ),
-                                        });
-                                    }
-                                }
-                            }
-                        }
-                        self.ocr_lines = Self::group_words_into_lines(&self.ocr_results);
-
-                        self.results = self
-                            .ocr_lines
-                            .iter()
-                            .map(|line| {
-                                line.words
-                                    .iter()
-                                    .map(|w| w.text.trim().to_string())
-                                    .collect::<Vec<_>>()
-                                    .join(" ")
-                            })
-                            .collect::<Vec<_>>()
-                            .join(" ");
-                    }
-                    Err(e) => eprintln!("Error de Tesseract: {:?}", e),
-                }
+        let Some(cropped_dyn_image) = self.selection_image() else {
+            return;
+        };
+        self.last_engine = Some(config::Engine::Tesseract);
+        self.recognition_failed = false;
+        let started = Instant::now();
+        let (preprocessed_image, transform) = self.profile.preprocess.apply(&cropped_dyn_image);
+        let preprocessed = Instant::now();
+
+        report!("Ejecutando OCR en la selección...");
+        match self.run_tesseract(&preprocessed_image) {
+            Ok(words) => {
+                let model = match self.tesseract_args.oem {
+                    Some(0) => "legacy",
+                    Some(1) => "lstm",
+                    Some(2) => "legacy+lstm",
+                    _ => "default",
+                };
+                self.recognition = export::Metadata {
+                    engine: "tesseract",
+                    model: model.to_string(),
+                    language: Some(self.tesseract_args.lang.clone()),
+                    preprocess_ms: (preprocessed - started).as_millis() as u64,
+                    recognition_ms: preprocessed.elapsed().as_millis() as u64,
+                };
+                self.set_ocr_lines(Self::lines_from_tesseract(words, transform), true);
+            }
+            Err(e) => {
+                eprintln!("Error de Tesseract: {}", e);
+                self.recognition_failed = true;
             }
         }
     }
@@ -679,11 +1801,14 @@ impl eframe::App for ScreenshotApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         #[cfg(any(feature = "ollama", feature = "gemini"))]
         self.poll_ai_result();
+        if self.autocopy_pending {
+            self.finish_autocopy(ctx);
+        }
 
         egui::CentralPanel::default()
             .frame(egui::Frame::none())
             .show(ctx, |ui| {
-                ui.image((self.texture_handle.id(), ui.available_size()));
+                self.draw_screenshot(ui);
                 let response = ui.interact(
                     ui.max_rect(),
                     ui.id().with("screenshot_area"),
@@ -704,54 +1829,89 @@ impl eframe::App for ScreenshotApp {
                             self.drag_mode = DragMode::BottomLeft;
                         } else if norm_sel.right_bottom().distance(pos) < handle_radius {
                             self.drag_mode = DragMode::BottomRight;
+                        } else if let Some(word) = self.word_at(pos) {
+                            self.drag_mode = DragMode::SelectingText;
+                            self.text_selection = Some((word, word));
                         } else {
                             self.drag_mode = DragMode::Creating;
                             self.drag_start = Some(pos);
                             self.selection = Some(egui::Rect::from_min_size(pos, egui::Vec2::ZERO));
-                            self.ocr_results.clear();
-                            self.ocr_lines.clear();
+                            self.clear_ocr();
                             self.menu_anchor_pos = None;
                         }
                     } else if let Some(pos) = pointer_pos {
                         self.drag_mode = DragMode::Creating;
                         self.drag_start = Some(pos);
                         self.selection = Some(egui::Rect::from_min_size(pos, egui::Vec2::ZERO));
-                        self.ocr_results.clear();
-                        self.ocr_lines.clear();
+                        self.clear_ocr();
                         self.menu_anchor_pos = None;
                     }
                 }
 
-                if response.dragged() {
-                    if let (Some(pos), Some(selection)) = (pointer_pos, &mut self.selection) {
-                        match self.drag_mode {
-                            DragMode::Creating => {
-                                if let Some(start_pos) = self.drag_start {
-                                    *selection = egui::Rect::from_two_pos(start_pos, pos);
-                                }
-                            }
-                            DragMode::TopLeft => {
-                                *selection = egui::Rect::from_two_pos(pos, selection.right_bottom())
-                            }
-                            DragMode::TopRight => {
-                                *selection = egui::Rect::from_two_pos(pos, selection.left_bottom())
-                            }
-                            DragMode::BottomLeft => {
-                                *selection = egui::Rect::from_two_pos(pos, selection.right_top())
-                            }
-                            DragMode::BottomRight => {
-                                *selection = egui::Rect::from_two_pos(pos, selection.left_top())
+                if response.dragged()
+                    && self.drag_mode == DragMode::SelectingText
+                    && let Some(word) = pointer_pos.and_then(|pos| self.word_at(pos))
+                    && let Some((start, _)) = self.text_selection
+                {
+                    self.text_selection = Some((start, word));
+                }
+
+                if response.dragged()
+                    && let (Some(pos), Some(selection)) = (pointer_pos, &mut self.selection)
+                {
+                    match self.drag_mode {
+                        DragMode::Creating => {
+                            if let Some(start_pos) = self.drag_start {
+                                *selection = egui::Rect::from_two_pos(start_pos, pos);
                             }
-                            DragMode::None => {}
                         }
+                        DragMode::TopLeft => {
+                            *selection = egui::Rect::from_two_pos(pos, selection.right_bottom())
+                        }
+                        DragMode::TopRight => {
+                            *selection = egui::Rect::from_two_pos(pos, selection.left_bottom())
+                        }
+                        DragMode::BottomLeft => {
+                            *selection = egui::Rect::from_two_pos(pos, selection.right_top())
+                        }
+                        DragMode::BottomRight => {
+                            *selection = egui::Rect::from_two_pos(pos, selection.left_top())
+                        }
+                        DragMode::None | DragMode::SelectingText => {}
                     }
                 }
 
-                if response.drag_stopped() {
+                if self.drag_mode == DragMode::SelectingText
+                    && (response.drag_stopped() || response.clicked())
+                {
+                    if let Some((start, end)) = self.text_selection {
+                        let range = if response.double_clicked() {
+                            self.line_range(start)
+                        } else {
+                            (start, end)
+                        };
+                        self.text_selection = Some(range);
+                        // Como en un terminal, la selección también va a la selección primaria.
+                        let text = self.words_text(range.0, range.1);
+                        self.copy(
+                            clipboard::Content::Text(text.clone()),
+                            clipboard::Selection::Primary,
+                        );
+                        self.copy_text(&text);
+                    }
+                    self.drag_mode = DragMode::None;
+                } else if response.drag_stopped() {
                     if let Some(selection) = &mut self.selection {
                         *selection = selection.normalized();
-                        #[cfg(feature = "tesseract")]
-                        self.perform_ocr();
+                        self.preview_texture = None;
+                        if self.profile.autocopy {
+                            self.autocopy_pending = true;
+                            self.run_default_engine();
+                            ctx.request_repaint();
+                        } else {
+                            #[cfg(feature = "tesseract")]
+                            self.perform_ocr();
+                        }
                     }
                     self.drag_mode = DragMode::None;
                     self.drag_start = None;
@@ -817,38 +1977,37 @@ impl eframe::App for ScreenshotApp {
                         corner_color,
                     );
 
-                    if self.drag_mode == DragMode::None {
-                        if let Some(pos) = pointer_pos {
-                            let handle_radius = 8.0;
-                            if selection_rect.left_top().distance(pos) < handle_radius
-                                || selection_rect.right_bottom().distance(pos) < handle_radius
-                            {
-                                ctx.set_cursor_icon(egui::CursorIcon::ResizeNwSe);
-                            } else if selection_rect.right_top().distance(pos) < handle_radius
-                                || selection_rect.left_bottom().distance(pos) < handle_radius
-                            {
-                                ctx.set_cursor_icon(egui::CursorIcon::ResizeNeSw);
-                            }
+                    if self.drag_mode == DragMode::None
+                        && let Some(pos) = pointer_pos
+                    {
+                        let handle_radius = 8.0;
+                        if selection_rect.left_top().distance(pos) < handle_radius
+                            || selection_rect.right_bottom().distance(pos) < handle_radius
+                        {
+                            ctx.set_cursor_icon(egui::CursorIcon::ResizeNwSe);
+                        } else if selection_rect.right_top().distance(pos) < handle_radius
+                            || selection_rect.left_bottom().distance(pos) < handle_radius
+                        {
+                            ctx.set_cursor_icon(egui::CursorIcon::ResizeNeSw);
                         }
                     }
 
+                    if matches!(self.drag_mode, DragMode::None | DragMode::SelectingText) {
+                        self.draw_text_layer(ctx, painter, response.hover_pos());
+                    }
+
                     if self.drag_mode == DragMode::None {
                         for line in &self.ocr_lines {
-                            let screen_bbox = egui::Rect::from_min_size(
-                                selection_rect.min + line.bbox.min.to_vec2(),
-                                line.bbox.size(),
-                            )
-                            .expand(2.0);
-                            painter.rect_filled(
-                                screen_bbox,
+                            if let Some(shape) = self.screen_shape(
+                                line.bbox,
+                                line.outline(),
+                                2.0,
                                 5.0,
                                 egui::Color32::from_rgba_unmultiplied(255, 255, 255, 50),
-                            );
-                            painter.rect_stroke(
-                                screen_bbox,
-                                5.0,
                                 egui::Stroke::new(1.0, egui::Color32::WHITE),
-                            );
+                            ) {
+                                painter.add(shape);
+                            }
                         }
                     }
 
@@ -868,24 +2027,65 @@ impl eframe::App for ScreenshotApp {
 }
 
 fn main() -> Result<(), eframe::Error> {
+    let cli = cli::Cli::parse();
+    VERBOSE.store(cli.verbose, Ordering::Relaxed);
+    if let Some(payload) = cli.serve_clipboard {
+        if let Err(e) = clipboard::serve(payload, cli.selection) {
+            eprintln!("[portapapeles] {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if cli.serve_notification {
+        if let Err(e) = notify::serve() {
+            eprintln!("[notificación] {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    let config = config::Config::load();
     let wayshot_connection =
         WayshotConnection::new().expect("No se pudo conectar al servidor Wayland.");
-    let screenshot = wayshot_connection
-        .screenshot_all(false)
-        .expect("Fallo al tomar la captura de pantalla.");
-    let (width, height) = screenshot.dimensions();
-    let raw_buffer = screenshot.into_raw();
-    let screenshot_app_image = image::RgbaImage::from_raw(width, height, raw_buffer)
-        .expect("No se pudo convertir el búfer.");
+    if cli.list_monitors {
+        capture::print_outputs(&wayshot_connection);
+        return Ok(());
+    }
+    let capture = match capture::capture(&wayshot_connection, cli.monitor.as_deref()) {
+        Ok(capture) => capture,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    for monitor in &capture.monitors {
+        verbose!(
+            "[captura] Salida {}: {:?}, {:?} píxeles por punto, escala {}",
+            monitor.name,
+            monitor.rect,
+            monitor.pixels_per_point(),
+            monitor.scale
+        );
+    }
+    verbose!(
+        "[captura] Origen {:?}, {:?} píxeles por punto",
+        capture.origin,
+        capture.scale
+    );
+
+    let mut viewport = egui::ViewportBuilder::default()
+        .with_decorations(false)
+        .with_fullscreen(true);
+    if cli.monitor.is_some() {
+        // En Wayland el compositor decide dónde abrir la ventana; en X11 esto elige el monitor.
+        viewport = viewport.with_position(capture.origin);
+    }
     let native_options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_decorations(false)
-            .with_fullscreen(true),
+        viewport,
         ..Default::default()
     };
     eframe::run_native(
         "OCR",
         native_options,
-        Box::new(|cc| Box::new(ScreenshotApp::new(cc, screenshot_app_image))),
+        Box::new(|cc| Box::new(ScreenshotApp::new(cc, capture, config, cli))),
     )
 }
@@ -0,0 +1,103 @@
+use crate::{clipboard, config};
+use notify_rust::Notification;
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+
+/// Líneas del resultado que se muestran en la notificación.
+const PREVIEW_LINES: usize = 3;
+/// Caracteres por línea antes de recortarla con "…".
+const PREVIEW_WIDTH: usize = 80;
+
+/// Muestra el resultado desde un proceso hijo que espera la acción elegida, para que las
+/// acciones sigan funcionando aunque la ventana ya se haya cerrado (p. ej. con `--autocopy`).
+pub fn show(text: &str) -> io::Result<()> {
+    let mut child = Command::new(std::env::current_exe()?)
+        .arg("--serve-notification")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()?;
+    let written = child
+        .stdin
+        .take()
+        .map_or(Ok(()), |mut stdin| stdin.write_all(text.as_bytes()));
+    // El hijo vive hasta que se cierra la notificación; se espera en otro hilo para que no
+    // quede como proceso zombi.
+    std::thread::spawn(move || child.wait());
+    written
+}
+
+/// Punto de entrada de `--serve-notification`: lee el texto de stdin, lo notifica por D-Bus y
+/// ejecuta la acción que elija el usuario.
+pub fn serve() -> Result<(), String> {
+    let mut text = String::new();
+    io::stdin()
+        .read_to_string(&mut text)
+        .map_err(|e| format!("No se pudo leer el texto a notificar: {}", e))?;
+
+    let handle = Notification::new()
+        .appname("captura-extractor")
+        .icon("accessories-text-editor")
+        .summary("Text recognized")
+        .body(&preview(&text))
+        .action("copy", "Copy")
+        .action("edit", "Open in editor")
+        .action("translate", "Translate")
+        .show()
+        .map_err(|e| format!("No se pudo mostrar la notificación: {}", e))?;
+
+    // Espera a que se elija una acción o se cierre la notificación ("__closed").
+    let mut action = String::new();
+    handle.wait_for_action(|chosen| action = chosen.to_string());
+
+    match action.as_str() {
+        "copy" => clipboard::copy(
+            clipboard::Content::Text(text),
+            clipboard::Selection::Clipboard,
+        )
+        .map_err(|e| format!("Error al copiar al portapapeles: {}", e)),
+        "edit" => {
+            let path =
+                std::env::temp_dir().join(format!("captura-extractor-{}.txt", std::process::id()));
+            std::fs::write(&path, &text)
+                .map_err(|e| format!("No se pudo escribir '{}': {}", path.display(), e))?;
+            open(path.as_os_str())
+        }
+        "translate" => open(config::Config::load().translate_url(&text).as_ref()),
+        _ => Ok(()),
+    }
+}
+
+/// Abre un archivo o una URL con la aplicación predeterminada.
+fn open(target: &std::ffi::OsStr) -> Result<(), String> {
+    Command::new("xdg-open")
+        .arg(target)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("No se pudo ejecutar xdg-open: {}", e))
+}
+
+/// Primeras líneas no vacías del texto, recortadas y escapadas para el marcado de las
+/// notificaciones.
+fn preview(text: &str) -> String {
+    let mut lines = text.lines().map(str::trim).filter(|l| !l.is_empty());
+    let mut preview: Vec<String> = lines
+        .by_ref()
+        .take(PREVIEW_LINES)
+        .map(|line| {
+            if line.chars().count() > PREVIEW_WIDTH {
+                let clipped: String = line.chars().take(PREVIEW_WIDTH).collect();
+                format!("{}…", clipped.trim_end())
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+    if lines.next().is_some() {
+        preview.push("…".to_string());
+    }
+    preview
+        .join("\n")
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
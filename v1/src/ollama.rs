@@ -29,7 +29,11 @@ impl OllamaClient {
         }
     }
 
-    pub async fn generate_stream(&self, image_bytes: Vec<u8>, sender: Sender<String>) {
+    pub async fn generate_stream(
+        &self,
+        image_bytes: Vec<u8>,
+        sender: Sender<Result<String, String>>,
+    ) {
         let base64_image = STANDARD.encode(&image_bytes);
         let image = Image::from_base64(&base64_image);
         let request =
@@ -40,7 +44,7 @@ impl OllamaClient {
                     match res {
                         Ok(responses) => {
                             for resp in responses {
-                                if sender.send(resp.response).is_err() {
+                                if sender.send(Ok(resp.response)).is_err() {
                                     eprintln!(
                                         "[ollama.rs] El receptor del canal se cerró. Terminando stream."
                                     );
@@ -51,7 +55,7 @@ impl OllamaClient {
                         Err(e) => {
                             let error_msg = format!("[ollama.rs] Error en el stream: {}", e);
                             eprintln!("{}", error_msg);
-                            let _ = sender.send(error_msg);
+                            let _ = sender.send(Err(error_msg));
                             break;
                         }
                     }
@@ -60,7 +64,7 @@ impl OllamaClient {
             Err(e) => {
                 let error_msg = format!("[ollama.rs] No se pudo iniciar el stream: {}", e);
                 eprintln!("{}", error_msg);
-                let _ = sender.send(error_msg);
+                let _ = sender.send(Err(error_msg));
             }
         }
     }
@@ -1,3 +1,6 @@
+// Con `paddle_onnx` solo se usan los tipos de resultado; el cliente del socket queda sin uso.
+#![cfg_attr(feature = "paddle_onnx", allow(dead_code))]
+
 use serde::Deserialize;
 use std::sync::mpsc::Sender;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
@@ -9,6 +12,8 @@ const SOCKET_FILE: &str = "/tmp/paddle_socket_unix";
 pub struct OcrResult {
     pub text: String,
     pub coordinates: Vec<[f32; 2]>,
+    /// Confianza del reconocimiento, entre 0 y 1; `None` si el servidor no la da.
+    pub score: Option<f32>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -17,6 +22,9 @@ struct RawOcrData {
     texts: Vec<String>,
     #[serde(rename = "rec_polys")]
     coordinates: Vec<Vec<[f32; 2]>>,
+    /// Algunos modelos del servidor no devuelven puntuaciones.
+    #[serde(rename = "rec_scores", default)]
+    scores: Vec<f32>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -25,6 +33,8 @@ struct RawResponse {
 }
 
 pub type OcrResponse = Vec<OcrResult>;
+/// Lo que recibe la interfaz: los resultados o el mensaje de error del motor.
+pub type OcrMessage = Result<OcrResponse, String>;
 
 #[derive(Debug, Clone)]
 pub struct PaddleClient;
@@ -34,19 +44,18 @@ impl PaddleClient {
         PaddleClient
     }
 
-    pub async fn recognize(&self, image_bytes: Vec<u8>, sender: Sender<OcrResponse>) {
+    pub async fn recognize(&self, image_bytes: Vec<u8>, sender: Sender<OcrMessage>) {
         if let Err(e) = self.recognize_internal(image_bytes, sender.clone()).await {
             let error_msg = format!("[PaddleClient] Error: {}", e);
-            let vec_err: OcrResponse = Vec::new();
             eprintln!("{}", error_msg);
-            let _ = sender.send(vec_err);
+            let _ = sender.send(Err(error_msg));
         }
     }
 
     async fn recognize_internal(
         &self,
         image_bytes: Vec<u8>,
-        sender: Sender<OcrResponse>,
+        sender: Sender<OcrMessage>,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let mut stream =
             match UnixStream::connect(SOCKET_FILE).await {
@@ -69,9 +78,8 @@ impl PaddleClient {
         let response_size = u64::from_be_bytes(response_len_header) as usize;
 
         if response_size == 0 {
-            let vec_err: OcrResponse = Vec::new();
             eprintln!("El servidor regreso vacio");
-            let _ = sender.send(vec_err);
+            let _ = sender.send(Ok(Vec::new()));
             return Ok(());
         }
 
@@ -81,15 +89,21 @@ impl PaddleClient {
 
         let raw_response: RawResponse = serde_json::from_str(&response_str)?;
 
+        let scores = raw_response.res.scores;
         let results: Vec<OcrResult> = raw_response
             .res
             .texts
             .into_iter()
-            .zip(raw_response.res.coordinates.into_iter())
-            .map(|(text, coordinates)| OcrResult { text, coordinates })
+            .zip(raw_response.res.coordinates)
+            .enumerate()
+            .map(|(i, (text, coordinates))| OcrResult {
+                text,
+                coordinates,
+                score: scores.get(i).copied(),
+            })
             .collect();
 
-        sender.send(results)?;
+        sender.send(Ok(results))?;
 
         Ok(())
     }
@@ -0,0 +1,597 @@
+use crate::paddle_client::{OcrMessage, OcrResponse, OcrResult};
+use image::{RgbImage, imageops::FilterType};
+use ort::session::Session;
+use ort::value::Tensor;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+
+const DET_MODEL: &str = "det.onnx";
+const REC_MODEL: &str = "rec.onnx";
+const DICT_FILE: &str = "dict.txt";
+
+// Valores por defecto de PaddleOCR 3.x (los mismos que usa servidorOcr/ocr_server.py).
+const DET_LIMIT_SIDE_LEN: u32 = 64;
+const DET_MAX_SIDE_LIMIT: u32 = 4000;
+const DET_THRESH: f32 = 0.3;
+const DET_BOX_THRESH: f32 = 0.6;
+const DET_UNCLIP_RATIO: f32 = 1.5;
+const DET_MIN_SIZE: f32 = 3.0;
+const DET_MAX_CANDIDATES: usize = 1000;
+const DET_MEAN: [f32; 3] = [0.485, 0.456, 0.406];
+const DET_STD: [f32; 3] = [0.229, 0.224, 0.225];
+
+const REC_HEIGHT: u32 = 48;
+const REC_MIN_WIDTH: u32 = 320;
+
+type Quad = [[f32; 2]; 4];
+
+struct Models {
+    det: Session,
+    rec: Session,
+    charset: Vec<String>,
+}
+
+#[derive(Clone)]
+pub struct PaddleOnnx {
+    model_dir: PathBuf,
+    models: Arc<Mutex<Option<Models>>>,
+}
+
+impl PaddleOnnx {
+    pub fn new(model_dir: PathBuf) -> Self {
+        PaddleOnnx {
+            model_dir,
+            models: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub async fn recognize(&self, image_bytes: Vec<u8>, sender: Sender<OcrMessage>) {
+        let engine = self.clone();
+        let result = tokio::task::spawn_blocking(move || engine.recognize_internal(&image_bytes))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|r| r.map_err(|e| e.to_string()));
+
+        let message = result.map_err(|e| {
+            let error_msg = format!("[PaddleOnnx] Error: {}", e);
+            eprintln!("{}", error_msg);
+            error_msg
+        });
+        let _ = sender.send(message);
+    }
+
+    fn recognize_internal(
+        &self,
+        image_bytes: &[u8],
+    ) -> Result<OcrResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let image = image::load_from_memory(image_bytes)?.to_rgb8();
+        if image.width() == 0 || image.height() == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut guard = self
+            .models
+            .lock()
+            .map_err(|_| "El mutex de los modelos está envenenado")?;
+        if guard.is_none() {
+            *guard = Some(Models::load(&self.model_dir)?);
+        }
+        let models = guard.as_mut().expect("modelos recién cargados");
+
+        let mut boxes = models.detect(&image)?;
+        sort_boxes(&mut boxes);
+
+        let mut results = Vec::with_capacity(boxes.len());
+        for quad in boxes {
+            let crop = rotate_crop(&image, &quad);
+            let (text, score) = models.recognize_line(&crop)?;
+            if text.trim().is_empty() {
+                continue;
+            }
+            results.push(OcrResult {
+                text,
+                coordinates: quad.to_vec(),
+                score: Some(score),
+            });
+        }
+
+        Ok(results)
+    }
+}
+
+impl Models {
+    fn load(dir: &Path) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let det_path = dir.join(DET_MODEL);
+        let rec_path = dir.join(REC_MODEL);
+        let dict_path = dir.join(DICT_FILE);
+        for path in [&det_path, &rec_path, &dict_path] {
+            if !path.exists() {
+                return Err(format!(
+                    "No se encontró '{}'. Configura 'paddle_model_dir' con los modelos PP-OCR en ONNX.",
+                    path.display()
+                )
+                .into());
+            }
+        }
+
+        println!("Cargando modelos PP-OCR desde {}...", dir.display());
+        let det = Session::builder()?.commit_from_file(&det_path)?;
+        let rec = Session::builder()?.commit_from_file(&rec_path)?;
+
+        // Índice 0 reservado para el blank de CTC; el espacio va al final (use_space_char).
+        let mut charset = vec![String::new()];
+        charset.extend(
+            std::fs::read_to_string(&dict_path)?
+                .lines()
+                .map(|l| l.trim_end_matches('\r').to_string()),
+        );
+        charset.push(" ".to_string());
+
+        Ok(Models { det, rec, charset })
+    }
+
+    fn detect(
+        &mut self,
+        image: &RgbImage,
+    ) -> Result<Vec<Quad>, Box<dyn std::error::Error + Send + Sync>> {
+        let (src_w, src_h) = image.dimensions();
+        let (det_w, det_h) = det_resize_dims(src_w, src_h);
+        let resized = image::imageops::resize(image, det_w, det_h, FilterType::Triangle);
+
+        let plane = (det_w * det_h) as usize;
+        let mut input = vec![0f32; 3 * plane];
+        for (i, pixel) in resized.pixels().enumerate() {
+            // PaddleOCR trabaja en BGR (cv2), la normalización se aplica en ese orden.
+            for c in 0..3 {
+                let value = pixel.0[2 - c] as f32 / 255.0;
+                input[c * plane + i] = (value - DET_MEAN[c]) / DET_STD[c];
+            }
+        }
+
+        let tensor = Tensor::from_array(([1usize, 3, det_h as usize, det_w as usize], input))?;
+        let outputs = self.det.run(ort::inputs![tensor])?;
+        let (shape, prob) = outputs[0].try_extract_tensor::<f32>()?;
+        let map_h = shape[shape.len() - 2] as u32;
+        let map_w = shape[shape.len() - 1] as u32;
+
+        let scale_x = src_w as f32 / map_w as f32;
+        let scale_y = src_h as f32 / map_h as f32;
+
+        let mut boxes = Vec::new();
+        for points in bitmap_components(prob, map_w, map_h, DET_THRESH)
+            .into_iter()
+            .take(DET_MAX_CANDIDATES)
+        {
+            let hull = convex_hull(points);
+            let Some((quad, short_side)) = min_area_rect(&hull) else {
+                continue;
+            };
+            if short_side < DET_MIN_SIZE {
+                continue;
+            }
+            if box_score(prob, map_w, map_h, &quad) < DET_BOX_THRESH {
+                continue;
+            }
+
+            let (quad, short_side) = unclip(&quad, DET_UNCLIP_RATIO);
+            if short_side < DET_MIN_SIZE + 2.0 {
+                continue;
+            }
+
+            let mut scaled = quad.map(|p| {
+                [
+                    (p[0] * scale_x).clamp(0.0, src_w as f32),
+                    (p[1] * scale_y).clamp(0.0, src_h as f32),
+                ]
+            });
+            order_points(&mut scaled);
+            let width = distance(scaled[0], scaled[1]);
+            let height = distance(scaled[0], scaled[3]);
+            if width <= 3.0 || height <= 3.0 {
+                continue;
+            }
+            boxes.push(scaled);
+        }
+
+        Ok(boxes)
+    }
+
+    fn recognize_line(
+        &mut self,
+        crop: &RgbImage,
+    ) -> Result<(String, f32), Box<dyn std::error::Error + Send + Sync>> {
+        let ratio = crop.width() as f32 / crop.height().max(1) as f32;
+        let target_w = ((REC_HEIGHT as f32 * ratio).ceil() as u32).max(1);
+        let input_w = target_w.max(REC_MIN_WIDTH);
+        let resized = image::imageops::resize(crop, target_w, REC_HEIGHT, FilterType::Triangle);
+
+        let plane = (input_w * REC_HEIGHT) as usize;
+        let mut input = vec![0f32; 3 * plane];
+        for (x, y, pixel) in resized.enumerate_pixels() {
+            let i = (y * input_w + x) as usize;
+            for c in 0..3 {
+                input[c * plane + i] = (pixel.0[2 - c] as f32 / 255.0 - 0.5) / 0.5;
+            }
+        }
+
+        let tensor =
+            Tensor::from_array(([1usize, 3, REC_HEIGHT as usize, input_w as usize], input))?;
+        let outputs = self.rec.run(ort::inputs![tensor])?;
+        let (shape, probs) = outputs[0].try_extract_tensor::<f32>()?;
+        let steps = shape[1] as usize;
+        let classes = shape[2] as usize;
+
+        Ok(ctc_decode(probs, steps, classes, &self.charset))
+    }
+}
+
+fn det_resize_dims(width: u32, height: u32) -> (u32, u32) {
+    let min_side = width.min(height) as f32;
+    let mut ratio = if min_side < DET_LIMIT_SIDE_LEN as f32 {
+        DET_LIMIT_SIDE_LEN as f32 / min_side
+    } else {
+        1.0
+    };
+    let max_side = width.max(height) as f32 * ratio;
+    if max_side > DET_MAX_SIDE_LIMIT as f32 {
+        ratio *= DET_MAX_SIDE_LIMIT as f32 / max_side;
+    }
+
+    let round32 = |v: f32| (((v / 32.0).round() as u32) * 32).max(32);
+    (
+        round32(width as f32 * ratio),
+        round32(height as f32 * ratio),
+    )
+}
+
+/// Componentes conexas (8-vecindad) del mapa binarizado; devuelve los píxeles de borde de cada una.
+fn bitmap_components(prob: &[f32], width: u32, height: u32, thresh: f32) -> Vec<Vec<[f32; 2]>> {
+    let (w, h) = (width as i32, height as i32);
+    let on =
+        |x: i32, y: i32| x >= 0 && y >= 0 && x < w && y < h && prob[(y * w + x) as usize] > thresh;
+
+    let mut visited = vec![false; prob.len()];
+    let mut components = Vec::new();
+    let mut stack = Vec::new();
+
+    for start in 0..prob.len() {
+        if visited[start] || prob[start] <= thresh {
+            continue;
+        }
+        visited[start] = true;
+        stack.push(start as i32);
+
+        let mut border = Vec::new();
+        while let Some(idx) = stack.pop() {
+            let (x, y) = (idx % w, idx / w);
+            if !(on(x - 1, y) && on(x + 1, y) && on(x, y - 1) && on(x, y + 1)) {
+                border.push([x as f32, y as f32]);
+            }
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let (nx, ny) = (x + dx, y + dy);
+                    if on(nx, ny) {
+                        let n = (ny * w + nx) as usize;
+                        if !visited[n] {
+                            visited[n] = true;
+                            stack.push(n as i32);
+                        }
+                    }
+                }
+            }
+        }
+        components.push(border);
+    }
+
+    components
+}
+
+fn cross(o: [f32; 2], a: [f32; 2], b: [f32; 2]) -> f32 {
+    (a[0] - o[0]) * (b[1] - o[1]) - (a[1] - o[1]) * (b[0] - o[0])
+}
+
+fn convex_hull(mut points: Vec<[f32; 2]>) -> Vec<[f32; 2]> {
+    points.sort_by(|a, b| a[0].total_cmp(&b[0]).then(a[1].total_cmp(&b[1])));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+
+    let mut hull: Vec<[f32; 2]> = Vec::with_capacity(points.len() * 2);
+    for pass in 0..2 {
+        let start = hull.len();
+        let iter: Box<dyn Iterator<Item = &[f32; 2]>> = if pass == 0 {
+            Box::new(points.iter())
+        } else {
+            Box::new(points.iter().rev())
+        };
+        for &p in iter {
+            while hull.len() >= start + 2
+                && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        hull.pop();
+    }
+    hull
+}
+
+/// Rectángulo de área mínima (equivalente a cv2.minAreaRect) y su lado corto.
+fn min_area_rect(hull: &[[f32; 2]]) -> Option<(Quad, f32)> {
+    if hull.is_empty() {
+        return None;
+    }
+    if hull.len() < 3 {
+        let min_x = hull.iter().map(|p| p[0]).fold(f32::INFINITY, f32::min);
+        let max_x = hull.iter().map(|p| p[0]).fold(f32::NEG_INFINITY, f32::max);
+        let min_y = hull.iter().map(|p| p[1]).fold(f32::INFINITY, f32::min);
+        let max_y = hull.iter().map(|p| p[1]).fold(f32::NEG_INFINITY, f32::max);
+        let quad = [
+            [min_x, min_y],
+            [max_x, min_y],
+            [max_x, max_y],
+            [min_x, max_y],
+        ];
+        return Some((quad, (max_x - min_x).min(max_y - min_y)));
+    }
+
+    let mut best: Option<(f32, Quad, f32)> = None;
+    for i in 0..hull.len() {
+        let a = hull[i];
+        let b = hull[(i + 1) % hull.len()];
+        let len = distance(a, b);
+        if len == 0.0 {
+            continue;
+        }
+        let u = [(b[0] - a[0]) / len, (b[1] - a[1]) / len];
+        let n = [-u[1], u[0]];
+
+        let (mut min_u, mut max_u, mut min_n, mut max_n) = (
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+        );
+        for p in hull {
+            let du = (p[0] - a[0]) * u[0] + (p[1] - a[1]) * u[1];
+            let dn = (p[0] - a[0]) * n[0] + (p[1] - a[1]) * n[1];
+            min_u = min_u.min(du);
+            max_u = max_u.max(du);
+            min_n = min_n.min(dn);
+            max_n = max_n.max(dn);
+        }
+
+        let area = (max_u - min_u) * (max_n - min_n);
+        if best
+            .as_ref()
+            .is_none_or(|(best_area, _, _)| area < *best_area)
+        {
+            let corner =
+                |su: f32, sn: f32| [a[0] + u[0] * su + n[0] * sn, a[1] + u[1] * su + n[1] * sn];
+            let quad = [
+                corner(min_u, min_n),
+                corner(max_u, min_n),
+                corner(max_u, max_n),
+                corner(min_u, max_n),
+            ];
+            best = Some((area, quad, (max_u - min_u).min(max_n - min_n)));
+        }
+    }
+
+    best.map(|(_, quad, short_side)| (quad, short_side))
+}
+
+fn point_in_quad(quad: &Quad, p: [f32; 2]) -> bool {
+    let mut sign = 0.0;
+    for i in 0..4 {
+        let c = cross(quad[i], quad[(i + 1) % 4], p);
+        if c != 0.0 {
+            if sign != 0.0 && c.signum() != sign {
+                return false;
+            }
+            sign = c.signum();
+        }
+    }
+    true
+}
+
+/// Probabilidad media dentro del polígono (box_score_fast de DBPostProcess).
+fn box_score(prob: &[f32], width: u32, height: u32, quad: &Quad) -> f32 {
+    let min_x = quad
+        .iter()
+        .map(|p| p[0])
+        .fold(f32::INFINITY, f32::min)
+        .floor()
+        .max(0.0) as u32;
+    let max_x = quad
+        .iter()
+        .map(|p| p[0])
+        .fold(f32::NEG_INFINITY, f32::max)
+        .ceil() as u32;
+    let min_y = quad
+        .iter()
+        .map(|p| p[1])
+        .fold(f32::INFINITY, f32::min)
+        .floor()
+        .max(0.0) as u32;
+    let max_y = quad
+        .iter()
+        .map(|p| p[1])
+        .fold(f32::NEG_INFINITY, f32::max)
+        .ceil() as u32;
+
+    let (mut sum, mut count) = (0.0, 0u32);
+    for y in min_y..=max_y.min(height - 1) {
+        for x in min_x..=max_x.min(width - 1) {
+            if point_in_quad(quad, [x as f32, y as f32]) {
+                sum += prob[(y * width + x) as usize];
+                count += 1;
+            }
+        }
+    }
+    if count == 0 { 0.0 } else { sum / count as f32 }
+}
+
+/// Expande el rectángulo una distancia área * ratio / perímetro, como hace pyclipper en PaddleOCR.
+fn unclip(quad: &Quad, ratio: f32) -> (Quad, f32) {
+    let w = distance(quad[0], quad[1]);
+    let h = distance(quad[0], quad[3]);
+    let perimeter = 2.0 * (w + h);
+    if perimeter == 0.0 {
+        return (*quad, 0.0);
+    }
+    let d = w * h * ratio / perimeter;
+
+    let unit = |a: [f32; 2], b: [f32; 2]| {
+        let len = distance(a, b).max(f32::EPSILON);
+        [(b[0] - a[0]) / len, (b[1] - a[1]) / len]
+    };
+    let u = unit(quad[0], quad[1]);
+    let v = unit(quad[0], quad[3]);
+    let shift = |p: [f32; 2], su: f32, sv: f32| {
+        [
+            p[0] + (u[0] * su + v[0] * sv) * d,
+            p[1] + (u[1] * su + v[1] * sv) * d,
+        ]
+    };
+
+    (
+        [
+            shift(quad[0], -1.0, -1.0),
+            shift(quad[1], 1.0, -1.0),
+            shift(quad[2], 1.0, 1.0),
+            shift(quad[3], -1.0, 1.0),
+        ],
+        (w + 2.0 * d).min(h + 2.0 * d),
+    )
+}
+
+/// Ordena las esquinas como arriba-izquierda, arriba-derecha, abajo-derecha, abajo-izquierda.
+fn order_points(quad: &mut Quad) {
+    let mut pts = *quad;
+    pts.sort_by(|a, b| a[0].total_cmp(&b[0]));
+    let (left, right) = pts.split_at_mut(2);
+    left.sort_by(|a, b| a[1].total_cmp(&b[1]));
+    right.sort_by(|a, b| a[1].total_cmp(&b[1]));
+    *quad = [left[0], right[0], right[1], left[1]];
+}
+
+/// Orden de lectura de las cajas detectadas (sorted_boxes de PaddleOCR).
+fn sort_boxes(boxes: &mut [Quad]) {
+    boxes.sort_by(|a, b| {
+        a[0][1]
+            .total_cmp(&b[0][1])
+            .then(a[0][0].total_cmp(&b[0][0]))
+    });
+    for i in 0..boxes.len().saturating_sub(1) {
+        for j in (0..=i).rev() {
+            if (boxes[j + 1][0][1] - boxes[j][0][1]).abs() < 10.0
+                && boxes[j + 1][0][0] < boxes[j][0][0]
+            {
+                boxes.swap(j, j + 1);
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+fn distance(a: [f32; 2], b: [f32; 2]) -> f32 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt()
+}
+
+fn sample_bilinear(image: &RgbImage, x: f32, y: f32) -> [u8; 3] {
+    let max_x = image.width() as f32 - 1.0;
+    let max_y = image.height() as f32 - 1.0;
+    let x = x.clamp(0.0, max_x);
+    let y = y.clamp(0.0, max_y);
+    let (x0, y0) = (x.floor(), y.floor());
+    let (x1, y1) = ((x0 + 1.0).min(max_x), (y0 + 1.0).min(max_y));
+    let (fx, fy) = (x - x0, y - y0);
+
+    let p = |px: f32, py: f32| image.get_pixel(px as u32, py as u32).0;
+    let (p00, p10, p01, p11) = (p(x0, y0), p(x1, y0), p(x0, y1), p(x1, y1));
+
+    let mut out = [0u8; 3];
+    for c in 0..3 {
+        let top = p00[c] as f32 * (1.0 - fx) + p10[c] as f32 * fx;
+        let bottom = p01[c] as f32 * (1.0 - fx) + p11[c] as f32 * fx;
+        out[c] = (top * (1.0 - fy) + bottom * fy).round() as u8;
+    }
+    out
+}
+
+/// Recorta la región de la caja enderezándola (get_rotate_crop_image de PaddleOCR).
+fn rotate_crop(image: &RgbImage, quad: &Quad) -> RgbImage {
+    let width = distance(quad[0], quad[1])
+        .max(distance(quad[2], quad[3]))
+        .round()
+        .max(1.0) as u32;
+    let height = distance(quad[0], quad[3])
+        .max(distance(quad[1], quad[2]))
+        .round()
+        .max(1.0) as u32;
+
+    let origin = quad[0];
+    let du = [
+        (quad[1][0] - origin[0]) / width as f32,
+        (quad[1][1] - origin[1]) / width as f32,
+    ];
+    let dv = [
+        (quad[3][0] - origin[0]) / height as f32,
+        (quad[3][1] - origin[1]) / height as f32,
+    ];
+
+    let crop = RgbImage::from_fn(width, height, |x, y| {
+        let (fx, fy) = (x as f32 + 0.5, y as f32 + 0.5);
+        image::Rgb(sample_bilinear(
+            image,
+            origin[0] + du[0] * fx + dv[0] * fy - 0.5,
+            origin[1] + du[1] * fx + dv[1] * fy - 0.5,
+        ))
+    });
+
+    if height as f32 / width as f32 >= 1.5 {
+        image::imageops::rotate270(&crop)
+    } else {
+        crop
+    }
+}
+
+/// Devuelve el texto y su confianza: la probabilidad media de los caracteres emitidos, como
+/// `CTCLabelDecode` de PaddleOCR.
+fn ctc_decode(probs: &[f32], steps: usize, classes: usize, charset: &[String]) -> (String, f32) {
+    let mut text = String::new();
+    let mut score = 0.0;
+    let mut chars = 0;
+    let mut last = 0usize;
+    for t in 0..steps {
+        let row = &probs[t * classes..(t + 1) * classes];
+        let (best, prob) = row
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(i, p)| (i, *p))
+            .unwrap_or((0, 0.0));
+        if best != 0
+            && best != last
+            && let Some(ch) = charset.get(best)
+        {
+            text.push_str(ch);
+            score += prob;
+            chars += 1;
+        }
+        last = best;
+    }
+    (
+        text,
+        if chars == 0 {
+            0.0
+        } else {
+            score / chars as f32
+        },
+    )
+}
@@ -0,0 +1,418 @@
+use crate::{OcrLine, quad};
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+use image::DynamicImage;
+use std::fmt::Write as _;
+use std::io::Write as _;
+
+/// Resolución con la que se pasa de píxeles de la captura a puntos de PDF.
+const DPI: f32 = 96.0;
+/// Ancho de cada glifo de la fuente invisible, en milésimas de em.
+const GLYPH_WIDTH: f32 = 500.0;
+/// Unidades por em de la fuente incrustada; así los anchos coinciden con los del PDF.
+const UNITS_PER_EM: u16 = 1000;
+
+/// Página con el recorte como imagen y, encima, el texto reconocido invisible (modo de
+/// renderizado 3) en la posición de cada palabra, para poder buscar y copiar.
+pub fn render(image: &DynamicImage, lines: &[OcrLine]) -> Vec<u8> {
+    let scale = 72.0 / DPI;
+    let rgb = image.to_rgb8();
+    let (width, height) = (rgb.width(), rgb.height());
+    let (page_w, page_h) = (width as f32 * scale, height as f32 * scale);
+
+    let mut content = format!(
+        "q {:.2} 0 0 {:.2} 0 0 cm /Im1 Do Q\nBT 3 Tr\n",
+        page_w, page_h
+    );
+    for line in lines {
+        for (i, word) in line.words.iter().enumerate() {
+            let text = word.text.trim();
+            if text.is_empty() {
+                continue;
+            }
+            let q = word.quad();
+            let size = (quad::height(&q) * scale).max(1.0);
+            let chars = text.chars().count() as f32;
+            let stretch = quad::width(&q) * scale / (chars * size * GLYPH_WIDTH / 1000.0) * 100.0;
+            // La Y del PDF crece hacia arriba; la línea base es el borde inferior de la caja.
+            let dir = quad::direction(&q);
+            let origin = q[3];
+            let _ = writeln!(
+                content,
+                "/F1 {:.2} Tf {:.2} Tz {:.4} {:.4} {:.4} {:.4} {:.2} {:.2} Tm <{}> Tj",
+                size,
+                stretch,
+                dir.x,
+                -dir.y,
+                dir.y,
+                dir.x,
+                origin.x * scale,
+                page_h - origin.y * scale,
+                utf16_hex(text, i + 1 < line.words.len())
+            );
+        }
+    }
+    content.push_str("ET\n");
+
+    let mut pdf = PdfWriter::default();
+    pdf.object("<< /Type /Catalog /Pages 2 0 R >>".into());
+    pdf.object("<< /Type /Pages /Kids [3 0 R] /Count 1 >>".into());
+    pdf.object(format!(
+        "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] \
+         /Resources << /XObject << /Im1 5 0 R >> /Font << /F1 6 0 R >> >> /Contents 4 0 R >>",
+        page_w, page_h
+    ));
+    pdf.stream(String::new(), content.into_bytes());
+    pdf.stream(
+        format!(
+            "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB \
+             /BitsPerComponent 8 /Filter /FlateDecode",
+            width, height
+        ),
+        deflate(rgb.as_raw()),
+    );
+    pdf.object(
+        "<< /Type /Font /Subtype /Type0 /BaseFont /GlyphLessFont /Encoding /Identity-H \
+         /DescendantFonts [7 0 R] /ToUnicode 9 0 R >>"
+            .into(),
+    );
+    pdf.object(format!(
+        "<< /Type /Font /Subtype /CIDFontType2 /BaseFont /GlyphLessFont \
+         /CIDSystemInfo << /Registry (Adobe) /Ordering (Identity) /Supplement 0 >> \
+         /FontDescriptor 8 0 R /DW {} /CIDToGIDMap 10 0 R >>",
+        GLYPH_WIDTH
+    ));
+    pdf.object(format!(
+        "<< /Type /FontDescriptor /FontName /GlyphLessFont /Flags 5 \
+         /FontBBox [0 0 {} 1000] /ItalicAngle 0 /Ascent 1000 /Descent 0 \
+         /CapHeight 1000 /StemV 80 /FontFile2 11 0 R >>",
+        GLYPH_WIDTH
+    ));
+    pdf.stream(String::new(), identity_cmap().into_bytes());
+    // Todos los CID usan el único glifo (vacío) de la fuente, como en el PDF de Tesseract.
+    let cid_to_gid: Vec<u8> = [0u8, 1].repeat(0x10000);
+    pdf.stream("/Filter /FlateDecode".into(), deflate(&cid_to_gid));
+    let font = glyphless_font();
+    pdf.stream(
+        format!("/Length1 {} /Filter /FlateDecode", font.len()),
+        deflate(&font),
+    );
+    pdf.finish()
+}
+
+/// Texto en UTF-16BE, que con `Identity-H` y el CMap identidad es a la vez CID y Unicode.
+/// Los caracteres fuera del plano básico se sustituyen por U+FFFD.
+fn utf16_hex(text: &str, trailing_space: bool) -> String {
+    let mut hex = String::new();
+    let spaced = trailing_space.then_some(' ');
+    for c in text.chars().chain(spaced) {
+        let code = u16::try_from(c as u32).unwrap_or(0xFFFD);
+        let _ = write!(hex, "{:04X}", code);
+    }
+    hex
+}
+
+fn identity_cmap() -> String {
+    let mut cmap = String::from(
+        "/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n\
+         /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+         /CMapName /Adobe-Identity-UCS def\n/CMapType 2 def\n\
+         1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n",
+    );
+    // Cada rango solo puede variar en el último byte y cada bloque admite 100 rangos.
+    let ranges: Vec<u16> = (0..=0xFF).collect();
+    for block in ranges.chunks(100) {
+        let _ = writeln!(cmap, "{} beginbfrange", block.len());
+        for high in block {
+            let _ = writeln!(cmap, "<{0:02X}00> <{0:02X}FF> <{0:02X}00>", high);
+        }
+        cmap.push_str("endbfrange\n");
+    }
+    cmap.push_str("endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend\n");
+    cmap
+}
+
+/// Fuente TrueType mínima con dos glifos vacíos (`.notdef` y el que usan todos los
+/// caracteres), para que la fuente de la capa de texto esté incrustada como exige PDF.
+fn glyphless_font() -> Vec<u8> {
+    let width = GLYPH_WIDTH as u16;
+    let mut head = Vec::new();
+    put_u32(&mut head, 0x0001_0000); // version
+    put_u32(&mut head, 0x0001_0000); // fontRevision
+    put_u32(&mut head, 0); // checkSumAdjustment, se rellena al final
+    put_u32(&mut head, 0x5F0F_3CF5); // magicNumber
+    put_u16(&mut head, 0b1011); // flags
+    put_u16(&mut head, UNITS_PER_EM);
+    head.extend_from_slice(&[0; 16]); // created, modified
+    for value in [0, 0, width, UNITS_PER_EM] {
+        put_u16(&mut head, value); // xMin, yMin, xMax, yMax
+    }
+    for value in [0, 8, 2, 0, 0] {
+        put_u16(&mut head, value); // macStyle, lowestRecPPEM, fontDirectionHint, formatos
+    }
+
+    let mut hhea = Vec::new();
+    put_u32(&mut hhea, 0x0001_0000);
+    // ascender, descender, lineGap, advanceWidthMax, minLSB, minRSB, xMaxExtent
+    for value in [UNITS_PER_EM, 0, 0, width, 0, 0, width] {
+        put_u16(&mut hhea, value);
+    }
+    // caretSlopeRise, caretSlopeRun, caretOffset, reservados y metricDataFormat
+    for value in [1, 0, 0, 0, 0, 0, 0, 0] {
+        put_u16(&mut hhea, value);
+    }
+    put_u16(&mut hhea, 2); // numberOfHMetrics
+
+    let mut maxp = Vec::new();
+    put_u32(&mut maxp, 0x0001_0000);
+    put_u16(&mut maxp, 2); // numGlyphs
+    // Sin contornos ni instrucciones; solo maxZones vale 2.
+    for value in [0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0] {
+        put_u16(&mut maxp, value);
+    }
+
+    let mut hmtx = Vec::new();
+    for _ in 0..2 {
+        put_u16(&mut hmtx, width);
+        put_u16(&mut hmtx, 0);
+    }
+
+    // Un único segmento (el final obligatorio en 0xFFFF) que no asigna ningún carácter.
+    let mut cmap = Vec::new();
+    for value in [0, 1, 3, 1] {
+        put_u16(&mut cmap, value);
+    }
+    put_u32(&mut cmap, 12);
+    for value in [4, 24, 0, 2, 2, 0, 0, 0xFFFF, 0, 0xFFFF, 1, 0] {
+        put_u16(&mut cmap, value);
+    }
+
+    let mut post = Vec::new();
+    put_u32(&mut post, 0x0003_0000);
+    put_u32(&mut post, 0); // italicAngle
+    put_u32(&mut post, 0); // underlinePosition, underlineThickness
+    put_u32(&mut post, 1); // isFixedPitch
+    post.extend_from_slice(&[0; 16]);
+
+    // Los dos glifos están vacíos: `loca` (formato corto) apunta siempre al inicio de `glyf`.
+    let loca = vec![0; 6];
+    let tables: [(&[u8; 4], Vec<u8>); 8] = [
+        (b"cmap", cmap),
+        (b"glyf", Vec::new()),
+        (b"head", head),
+        (b"hhea", hhea),
+        (b"hmtx", hmtx),
+        (b"loca", loca),
+        (b"maxp", maxp),
+        (b"post", post),
+    ];
+
+    let count = tables.len() as u16;
+    let entry_selector = count.ilog2() as u16;
+    let search_range = (1 << entry_selector) * 16;
+    let range_shift = count * 16 - search_range;
+    let mut font = Vec::new();
+    put_u32(&mut font, 0x0001_0000);
+    for value in [count, search_range, entry_selector, range_shift] {
+        put_u16(&mut font, value);
+    }
+    let mut offset = 12 + 16 * tables.len();
+    let mut head_offset = 0;
+    for (tag, data) in &tables {
+        if *tag == b"head" {
+            head_offset = offset;
+        }
+        font.extend_from_slice(*tag);
+        put_u32(&mut font, checksum(data));
+        put_u32(&mut font, offset as u32);
+        put_u32(&mut font, data.len() as u32);
+        offset += data.len().next_multiple_of(4);
+    }
+    for (_, data) in &tables {
+        font.extend_from_slice(data);
+        font.resize(font.len().next_multiple_of(4), 0);
+    }
+
+    let adjustment = 0xB1B0_AFBAu32.wrapping_sub(checksum(&font));
+    font[head_offset + 8..head_offset + 12].copy_from_slice(&adjustment.to_be_bytes());
+    font
+}
+
+fn put_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+/// Suma de comprobación de TrueType: palabras de 32 bits big-endian, con relleno de ceros.
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0u8; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    // Escribir en un `Vec` no puede fallar.
+    let _ = encoder.write_all(data);
+    encoder.finish().unwrap_or_default()
+}
+
+/// Objetos numerados desde 1 en el orden en que se añaden.
+#[derive(Default)]
+struct PdfWriter {
+    objects: Vec<Vec<u8>>,
+}
+
+impl PdfWriter {
+    fn object(&mut self, body: String) {
+        self.objects.push(body.into_bytes());
+    }
+
+    fn stream(&mut self, dictionary: String, data: Vec<u8>) {
+        let mut body =
+            format!("<< {} /Length {} >>\nstream\n", dictionary, data.len()).into_bytes();
+        body.extend_from_slice(&data);
+        body.extend_from_slice(b"\nendstream");
+        self.objects.push(body);
+    }
+
+    fn finish(self) -> Vec<u8> {
+        let mut out = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+        let mut offsets = Vec::with_capacity(self.objects.len());
+        for (i, body) in self.objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+            out.extend_from_slice(body);
+            out.extend_from_slice(b"\nendobj\n");
+        }
+
+        let xref = out.len();
+        let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", self.objects.len() + 1);
+        for offset in offsets {
+            let _ = writeln!(table, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(
+            table,
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            self.objects.len() + 1,
+            xref
+        );
+        out.extend_from_slice(table.as_bytes());
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OcrWord;
+
+    fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        haystack.windows(needle.len()).position(|w| w == needle)
+    }
+
+    /// Número tras la última aparición de `keyword`.
+    fn number_after(pdf: &[u8], keyword: &str) -> usize {
+        let start = pdf
+            .windows(keyword.len())
+            .rposition(|w| w == keyword.as_bytes())
+            .expect(keyword)
+            + keyword.len();
+        let digits: String = pdf[start..]
+            .iter()
+            .map(|&b| b as char)
+            .skip_while(|c| c.is_whitespace())
+            .take_while(char::is_ascii_digit)
+            .collect();
+        digits.parse().expect("número")
+    }
+
+    fn sample() -> Vec<u8> {
+        let image = DynamicImage::ImageRgb8(image::RgbImage::new(20, 10));
+        let lines = [OcrLine::test(
+            vec![OcrWord::test("Hola", 2.0, 2.0, 12.0, 6.0)],
+            0,
+        )];
+        render(&image, &lines)
+    }
+
+    #[test]
+    fn xref_offsets_point_at_objects() {
+        let pdf = sample();
+        let xref = number_after(&pdf, "startxref");
+        assert_eq!(find(&pdf[xref..], b"xref\n"), Some(0));
+
+        let table = String::from_utf8_lossy(&pdf[xref..]).into_owned();
+        let mut rows = table.lines().skip(1);
+        let count: usize = rows
+            .next()
+            .and_then(|header| header.split(' ').nth(1))
+            .and_then(|n| n.parse().ok())
+            .expect("cabecera de la tabla");
+        assert_eq!(number_after(&pdf, "/Size"), count);
+        assert_eq!(rows.next(), Some("0000000000 65535 f "));
+        for object in 1..count {
+            let row = rows.next().expect("entrada de la tabla");
+            assert_eq!(row.len(), 19, "{:?}", row);
+            let offset: usize = row[..10].parse().expect("desplazamiento");
+            let header = format!("{} 0 obj\n", object);
+            assert!(
+                pdf[offset..].starts_with(header.as_bytes()),
+                "objeto {}",
+                object
+            );
+        }
+    }
+
+    #[test]
+    fn stream_lengths_match() {
+        let pdf = sample();
+        let mut rest = &pdf[..];
+        let mut streams = 0;
+        while let Some(start) = find(rest, b"/Length ") {
+            let length = number_after(&rest[..(start + 20).min(rest.len())], "/Length");
+            let data = start + find(&rest[start..], b"stream\n").expect("stream") + 7;
+            assert!(rest[data + length..].starts_with(b"\nendstream"));
+            rest = &rest[data + length..];
+            streams += 1;
+        }
+        assert!(streams >= 3);
+    }
+
+    #[test]
+    fn embeds_consistent_font() {
+        let font = glyphless_font();
+        assert_eq!(checksum(&font), 0xB1B0_AFBA);
+        let count = u16::from_be_bytes([font[4], font[5]]) as usize;
+        let mut tags = Vec::new();
+        for record in font[12..12 + 16 * count].chunks(16) {
+            let field = |at: usize| {
+                u32::from_be_bytes(record[at..at + 4].try_into().expect("4 bytes")) as usize
+            };
+            let (sum, offset, length) = (field(4), field(8), field(12));
+            assert_eq!(offset % 4, 0);
+            assert!(offset + length <= font.len());
+            // La suma de `head` se calcula con `checkSumAdjustment` a cero.
+            if &record[..4] != b"head" {
+                assert_eq!(checksum(&font[offset..offset + length]) as usize, sum);
+            }
+            tags.push(&record[..4]);
+        }
+        assert!(tags.is_sorted());
+
+        let pdf = sample();
+        assert!(find(&pdf, b"/FontFile2 11 0 R").is_some());
+        assert!(find(&pdf, format!("/Length1 {}", font.len()).as_bytes()).is_some());
+    }
+
+    #[test]
+    fn encodes_text_as_utf16() {
+        assert_eq!(utf16_hex("añ", true), "006100F10020");
+        assert_eq!(utf16_hex("😀", false), "FFFD");
+    }
+}
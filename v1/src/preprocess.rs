@@ -0,0 +1,665 @@
+use eframe::egui;
+use image::{DynamicImage, GrayImage, Rgba, RgbaImage, imageops::FilterType};
+use serde::{Deserialize, Serialize};
+
+/// Desplazamiento respecto a la media local del umbral adaptativo.
+const ADAPTIVE_OFFSET: i32 = 7;
+/// Inclinación máxima que se intenta corregir y mínima que merece la pena corregir.
+const MAX_SKEW_DEGREES: f32 = 10.0;
+const MIN_SKEW_DEGREES: f32 = 0.3;
+/// Lado máximo de la imagen reducida con la que se estima la inclinación.
+const SKEW_ESTIMATE_SIDE: f32 = 600.0;
+/// Altura de carácter (en píxeles) con la que mejor trabajan Tesseract y PaddleOCR.
+const TARGET_TEXT_HEIGHT: f32 = 30.0;
+const AUTO_SCALE_RANGE: std::ops::RangeInclusive<f32> = 0.5..=4.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Threshold {
+    None,
+    Fixed,
+    Otsu,
+    Adaptive,
+}
+
+impl Threshold {
+    pub const ALL: [Threshold; 4] = [
+        Threshold::None,
+        Threshold::Fixed,
+        Threshold::Otsu,
+        Threshold::Adaptive,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Threshold::None => "None",
+            Threshold::Fixed => "Fixed",
+            Threshold::Otsu => "Otsu",
+            Threshold::Adaptive => "Adaptive",
+        }
+    }
+}
+
+/// Pasos aplicados al recorte antes de enviarlo al motor de OCR, en este orden:
+/// rotación y enderezado, escala de grises, escalado, contraste, desenfoque, enfoque, inversión y umbral.
+/// La detección de fondo oscuro se hace sobre el recorte original.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preprocess {
+    /// Tesseract siempre usa los filtros; el resto de motores solo si está activo. El giro y el
+    /// enderezado se aplican siempre.
+    pub all_engines: bool,
+    /// Giro manual en grados (0, 90, 180 o 270); es propio de cada captura y no se guarda.
+    #[serde(skip)]
+    pub rotation: u16,
+    /// Estima la inclinación del texto y la corrige si es pequeña.
+    pub deskew: bool,
+    pub grayscale: bool,
+    /// Calcula la escala a partir de la altura estimada del texto en vez de usar `scale`.
+    pub auto_scale: bool,
+    pub scale: f32,
+    /// Porcentaje de contraste (`0` lo desactiva).
+    pub contrast: f32,
+    /// Sigma del desenfoque gaussiano para eliminar ruido (`0` lo desactiva).
+    pub denoise: f32,
+    /// Sigma de la máscara de enfoque (`0` la desactiva).
+    pub sharpen: f32,
+    /// Invierte automáticamente si el histograma indica texto claro sobre fondo oscuro.
+    pub auto_invert: bool,
+    pub invert: bool,
+    pub threshold: Threshold,
+    pub threshold_level: u8,
+    /// Radio en píxeles de la ventana del umbral adaptativo.
+    pub adaptive_radius: u32,
+}
+
+impl Default for Preprocess {
+    fn default() -> Self {
+        Preprocess {
+            all_engines: false,
+            rotation: 0,
+            deskew: true,
+            grayscale: true,
+            auto_scale: true,
+            scale: 2.0,
+            contrast: 1.5,
+            denoise: 1.0,
+            sharpen: 0.0,
+            auto_invert: true,
+            invert: false,
+            threshold: Threshold::Fixed,
+            threshold_level: 128,
+            adaptive_radius: 15,
+        }
+    }
+}
+
+/// Transformación afín que lleva coordenadas de la imagen preprocesada a las del recorte original:
+/// `x = m[0]·x' + m[1]·y' + m[2]`, `y = m[3]·x' + m[4]·y' + m[5]`.
+#[derive(Debug, Clone, Copy)]
+pub struct Transform {
+    m: [f32; 6],
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        m: [1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+    };
+
+    fn scale(sx: f32, sy: f32) -> Self {
+        Transform {
+            m: [1.0 / sx, 0.0, 0.0, 0.0, 1.0 / sy, 0.0],
+        }
+    }
+
+    /// Aplica primero `step` (de la nueva imagen a la anterior) y después `self`.
+    fn then(self, step: Transform) -> Self {
+        let (a, s) = (self.m, step.m);
+        Transform {
+            m: [
+                a[0] * s[0] + a[1] * s[3],
+                a[0] * s[1] + a[1] * s[4],
+                a[0] * s[2] + a[1] * s[5] + a[2],
+                a[3] * s[0] + a[4] * s[3],
+                a[3] * s[1] + a[4] * s[4],
+                a[3] * s[2] + a[4] * s[5] + a[5],
+            ],
+        }
+    }
+
+    pub fn source_point(self, point: egui::Pos2) -> egui::Pos2 {
+        let m = self.m;
+        egui::pos2(
+            m[0] * point.x + m[1] * point.y + m[2],
+            m[3] * point.x + m[4] * point.y + m[5],
+        )
+    }
+
+    /// Caja alineada a los ejes que contiene el rectángulo una vez deshecha la rotación.
+    #[cfg(feature = "tesseract")]
+    pub fn source_rect(self, rect: egui::Rect) -> egui::Rect {
+        let corners = [
+            rect.left_top(),
+            rect.right_top(),
+            rect.right_bottom(),
+            rect.left_bottom(),
+        ]
+        .map(|p| self.source_point(p));
+        egui::Rect::from_points(&corners)
+    }
+}
+
+impl Preprocess {
+    /// Giro manual y enderezado, el primer paso de `apply` y lo único que reciben los motores
+    /// sin `all_engines`.
+    fn orient(&self, image: &DynamicImage) -> (DynamicImage, Transform) {
+        let (width, height) = (image.width() as f32, image.height() as f32);
+        let (mut image, mut transform) = match self.rotation {
+            90 => (
+                image.rotate90(),
+                Transform {
+                    m: [0.0, 1.0, 0.0, -1.0, 0.0, height],
+                },
+            ),
+            180 => (
+                image.rotate180(),
+                Transform {
+                    m: [-1.0, 0.0, width, 0.0, -1.0, height],
+                },
+            ),
+            270 => (
+                image.rotate270(),
+                Transform {
+                    m: [0.0, -1.0, width, 1.0, 0.0, 0.0],
+                },
+            ),
+            _ => (image.clone(), Transform::IDENTITY),
+        };
+
+        if self.deskew {
+            let angle = estimate_skew(&image.to_luma8());
+            if angle.abs() >= MIN_SKEW_DEGREES {
+                verbose!("[preprocess] Inclinación estimada: {:.1}°", -angle);
+                let (rotated, step) = rotate(&image.to_rgba8(), angle);
+                image = DynamicImage::ImageRgba8(rotated);
+                transform = transform.then(step);
+            }
+        }
+
+        (image, transform)
+    }
+
+    /// Lo que recibe un motor: todos se benefician del giro y el enderezado; los filtros solo
+    /// se aplican a Tesseract o, con `all_engines`, a cualquiera.
+    pub fn for_engine(&self, image: &DynamicImage, tesseract: bool) -> (DynamicImage, Transform) {
+        if tesseract || self.all_engines {
+            self.apply(image)
+        } else {
+            self.orient(image)
+        }
+    }
+
+    pub fn apply(&self, image: &DynamicImage) -> (DynamicImage, Transform) {
+        let (image, transform) = self.orient(image);
+        self.filter(image, transform)
+    }
+
+    /// Escala de grises, escalado, contraste, desenfoque, enfoque, inversión y umbral.
+    fn filter(&self, image: DynamicImage, mut transform: Transform) -> (DynamicImage, Transform) {
+        let invert = self.invert || (self.auto_invert && is_dark_background(&image.to_luma8()));
+
+        let mut image = if self.grayscale || self.threshold != Threshold::None {
+            DynamicImage::ImageLuma8(image.to_luma8())
+        } else {
+            image
+        };
+
+        let scale = if self.auto_scale {
+            self.auto_scale_factor(&image.to_luma8())
+        } else {
+            self.scale
+        };
+        if (scale - 1.0).abs() > f32::EPSILON {
+            let width = ((image.width() as f32 * scale).round() as u32).max(1);
+            let height = ((image.height() as f32 * scale).round() as u32).max(1);
+            transform = transform.then(Transform::scale(
+                width as f32 / image.width() as f32,
+                height as f32 / image.height() as f32,
+            ));
+            image = image.resize_exact(width, height, FilterType::Lanczos3);
+        }
+
+        if self.contrast != 0.0 {
+            image = image.adjust_contrast(self.contrast);
+        }
+        if self.denoise > 0.0 {
+            image = image.blur(self.denoise);
+        }
+        if self.sharpen > 0.0 {
+            image = image.unsharpen(self.sharpen, 1);
+        }
+        if invert {
+            image.invert();
+        }
+
+        let image = match self.threshold {
+            Threshold::None => image,
+            Threshold::Fixed => {
+                DynamicImage::ImageLuma8(binarize(image.to_luma8(), self.threshold_level))
+            }
+            Threshold::Otsu => {
+                let gray = image.to_luma8();
+                let level = otsu_level(&gray);
+                DynamicImage::ImageLuma8(binarize(gray, level))
+            }
+            Threshold::Adaptive => DynamicImage::ImageLuma8(adaptive_threshold(
+                &image.to_luma8(),
+                self.adaptive_radius,
+            )),
+        };
+
+        (image, transform)
+    }
+
+    fn auto_scale_factor(&self, gray: &GrayImage) -> f32 {
+        match estimate_text_height(gray) {
+            Some(text_height) => {
+                let scale = (TARGET_TEXT_HEIGHT / text_height)
+                    .clamp(*AUTO_SCALE_RANGE.start(), *AUTO_SCALE_RANGE.end());
+                verbose!(
+                    "[preprocess] Altura de texto estimada: {:.1}px, escala {:.2}",
+                    text_height,
+                    scale
+                );
+                scale
+            }
+            None => {
+                verbose!(
+                    "[preprocess] No se pudo estimar la altura del texto, escala {:.2}",
+                    self.scale
+                );
+                self.scale
+            }
+        }
+    }
+}
+
+/// Mediana de la altura de las componentes conexas del texto (separado del fondo con Otsu),
+/// descartando el ruido y las componentes demasiado grandes para ser caracteres.
+fn estimate_text_height(gray: &GrayImage) -> Option<f32> {
+    let (width, height) = gray.dimensions();
+    let level = otsu_level(gray);
+    let dark = gray.pixels().filter(|p| p.0[0] <= level).count();
+    let text_is_dark = dark * 2 < gray.pixels().len();
+    let is_text: Vec<bool> = gray
+        .pixels()
+        .map(|p| (p.0[0] <= level) == text_is_dark)
+        .collect();
+
+    let (w, h) = (width as i32, height as i32);
+    let mut visited = vec![false; is_text.len()];
+    let mut stack = Vec::new();
+    let mut heights = Vec::new();
+
+    for start in 0..is_text.len() {
+        if visited[start] || !is_text[start] {
+            continue;
+        }
+        visited[start] = true;
+        stack.push(start as i32);
+
+        let (mut min_x, mut max_x, mut min_y, mut max_y) = (w, 0, h, 0);
+        while let Some(idx) = stack.pop() {
+            let (x, y) = (idx % w, idx / w);
+            min_x = min_x.min(x);
+            max_x = max_x.max(x);
+            min_y = min_y.min(y);
+            max_y = max_y.max(y);
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let (nx, ny) = (x + dx, y + dy);
+                    if nx < 0 || ny < 0 || nx >= w || ny >= h {
+                        continue;
+                    }
+                    let n = (ny * w + nx) as usize;
+                    if is_text[n] && !visited[n] {
+                        visited[n] = true;
+                        stack.push(n as i32);
+                    }
+                }
+            }
+        }
+
+        let (cw, ch) = (max_x - min_x + 1, max_y - min_y + 1);
+        if ch >= 4 && ch < h * 9 / 10 && cw < ch * 5 {
+            heights.push(ch);
+        }
+    }
+
+    if heights.len() < 3 {
+        return None;
+    }
+    heights.sort_unstable();
+    Some(heights[heights.len() / 2] as f32)
+}
+
+/// Separa el histograma en dos clases con Otsu y considera fondo la más numerosa.
+fn is_dark_background(gray: &GrayImage) -> bool {
+    let level = otsu_level(gray);
+    let total = gray.pixels().len();
+    if total == 0 {
+        return false;
+    }
+    let dark = gray.pixels().filter(|p| p.0[0] <= level).count();
+    let dark_ratio = dark as f32 / total as f32;
+    let is_dark = dark_ratio > 0.5;
+
+    verbose!(
+        "[preprocess] Umbral {}, {:.0}% de píxeles oscuros: {}",
+        level,
+        dark_ratio * 100.0,
+        if is_dark {
+            "fondo oscuro, se invierte la imagen"
+        } else {
+            "fondo claro, no se invierte"
+        }
+    );
+    is_dark
+}
+
+/// Ángulo (en grados) que endereza las líneas de texto, buscando el giro cuya
+/// proyección horizontal de los píxeles de texto tiene los picos más marcados.
+fn estimate_skew(gray: &GrayImage) -> f32 {
+    let factor = (SKEW_ESTIMATE_SIDE / gray.width().max(gray.height()).max(1) as f32).min(1.0);
+    let small = if factor < 1.0 {
+        image::imageops::resize(
+            gray,
+            ((gray.width() as f32 * factor) as u32).max(1),
+            ((gray.height() as f32 * factor) as u32).max(1),
+            FilterType::Triangle,
+        )
+    } else {
+        gray.clone()
+    };
+
+    let level = otsu_level(&small);
+    let dark = small.pixels().filter(|p| p.0[0] <= level).count();
+    let text_is_dark = dark * 2 < small.pixels().len();
+    let points: Vec<(f32, f32)> = small
+        .enumerate_pixels()
+        .filter(|(_, _, p)| (p.0[0] <= level) == text_is_dark)
+        .map(|(x, y, _)| (x as f32, y as f32))
+        .collect();
+    if points.len() < 20 {
+        return 0.0;
+    }
+
+    let (width, height) = small.dimensions();
+    let offset = width as f32;
+    let bins = (2 * (width + height) + 2) as usize;
+    let score = |degrees: f32| {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let mut histogram = vec![0u32; bins];
+        for &(x, y) in &points {
+            let row = (sin * x + cos * y + offset).round() as usize;
+            histogram[row.min(bins - 1)] += 1;
+        }
+        histogram.iter().map(|&c| (c as f64).powi(2)).sum::<f64>()
+    };
+    let best_in = |from: f32, to: f32, step: f32, initial: f32| {
+        let mut best = (initial, score(initial));
+        let mut angle = from;
+        while angle <= to {
+            let value = score(angle);
+            if value > best.1 {
+                best = (angle, value);
+            }
+            angle += step;
+        }
+        best.0
+    };
+
+    let coarse = best_in(-MAX_SKEW_DEGREES, MAX_SKEW_DEGREES, 0.5, 0.0);
+    best_in(coarse - 0.5, coarse + 0.5, 0.1, coarse)
+}
+
+/// Gira la imagen `degrees` grados ampliando el lienzo; el hueco se rellena con el color del borde.
+fn rotate(image: &RgbaImage, degrees: f32) -> (RgbaImage, Transform) {
+    let (width, height) = (image.width() as f32, image.height() as f32);
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let new_width = (width * cos.abs() + height * sin.abs()).ceil().max(1.0);
+    let new_height = (width * sin.abs() + height * cos.abs()).ceil().max(1.0);
+
+    let (cx, cy) = (width / 2.0, height / 2.0);
+    let (ncx, ncy) = (new_width / 2.0, new_height / 2.0);
+    let transform = Transform {
+        m: [
+            cos,
+            sin,
+            cx - cos * ncx - sin * ncy,
+            -sin,
+            cos,
+            cy + sin * ncx - cos * ncy,
+        ],
+    };
+
+    let fill = border_color(image);
+    let rotated = RgbaImage::from_fn(new_width as u32, new_height as u32, |x, y| {
+        let source = transform.source_point(egui::pos2(x as f32 + 0.5, y as f32 + 0.5));
+        sample_bilinear(image, source.x - 0.5, source.y - 0.5).unwrap_or(fill)
+    });
+    (rotated, transform)
+}
+
+fn sample_bilinear(image: &RgbaImage, x: f32, y: f32) -> Option<Rgba<u8>> {
+    let (width, height) = image.dimensions();
+    if x < -0.5 || y < -0.5 || x > width as f32 - 0.5 || y > height as f32 - 0.5 {
+        return None;
+    }
+    let x = x.clamp(0.0, (width - 1) as f32);
+    let y = y.clamp(0.0, (height - 1) as f32);
+    let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+    let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+    let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+
+    let mut pixel = [0u8; 4];
+    for (c, value) in pixel.iter_mut().enumerate() {
+        let top = image.get_pixel(x0, y0).0[c] as f32 * (1.0 - fx)
+            + image.get_pixel(x1, y0).0[c] as f32 * fx;
+        let bottom = image.get_pixel(x0, y1).0[c] as f32 * (1.0 - fx)
+            + image.get_pixel(x1, y1).0[c] as f32 * fx;
+        *value = (top * (1.0 - fy) + bottom * fy).round() as u8;
+    }
+    Some(Rgba(pixel))
+}
+
+/// Color medio del borde, que suele ser el del fondo.
+fn border_color(image: &RgbaImage) -> Rgba<u8> {
+    let (width, height) = image.dimensions();
+    let mut sum = [0u64; 4];
+    let mut count = 0u64;
+    for (x, y, pixel) in image.enumerate_pixels() {
+        if x == 0 || y == 0 || x + 1 == width || y + 1 == height {
+            for (c, total) in sum.iter_mut().enumerate() {
+                *total += pixel.0[c] as u64;
+            }
+            count += 1;
+        }
+    }
+    let count = count.max(1);
+    Rgba(sum.map(|total| (total / count) as u8))
+}
+
+fn binarize(mut gray: GrayImage, level: u8) -> GrayImage {
+    for pixel in gray.pixels_mut() {
+        pixel.0[0] = if pixel.0[0] > level { 255 } else { 0 };
+    }
+    gray
+}
+
+/// Umbral que maximiza la varianza entre clases del histograma.
+fn otsu_level(gray: &GrayImage) -> u8 {
+    let mut histogram = [0u64; 256];
+    for pixel in gray.pixels() {
+        histogram[pixel.0[0] as usize] += 1;
+    }
+
+    let total = gray.pixels().len() as f64;
+    let sum: f64 = histogram
+        .iter()
+        .enumerate()
+        .map(|(i, &count)| i as f64 * count as f64)
+        .sum();
+
+    let (mut sum_background, mut weight_background) = (0.0, 0.0);
+    let (mut best_level, mut best_variance) = (0u8, 0.0);
+    for (level, &count) in histogram.iter().enumerate() {
+        weight_background += count as f64;
+        if weight_background == 0.0 {
+            continue;
+        }
+        let weight_foreground = total - weight_background;
+        if weight_foreground == 0.0 {
+            break;
+        }
+        sum_background += level as f64 * count as f64;
+        let mean_background = sum_background / weight_background;
+        let mean_foreground = (sum - sum_background) / weight_foreground;
+        let variance =
+            weight_background * weight_foreground * (mean_background - mean_foreground).powi(2);
+        if variance > best_variance {
+            best_variance = variance;
+            best_level = level as u8;
+        }
+    }
+    best_level
+}
+
+/// Compara cada píxel con la media de su vecindario usando una imagen integral.
+fn adaptive_threshold(gray: &GrayImage, radius: u32) -> GrayImage {
+    let (width, height) = gray.dimensions();
+    let stride = width as usize + 1;
+    let mut integral = vec![0u64; stride * (height as usize + 1)];
+    for y in 0..height as usize {
+        let mut row_sum = 0u64;
+        for x in 0..width as usize {
+            row_sum += gray.get_pixel(x as u32, y as u32).0[0] as u64;
+            integral[(y + 1) * stride + x + 1] = integral[y * stride + x + 1] + row_sum;
+        }
+    }
+
+    GrayImage::from_fn(width, height, |x, y| {
+        let x0 = x.saturating_sub(radius) as usize;
+        let y0 = y.saturating_sub(radius) as usize;
+        let x1 = (x + radius + 1).min(width) as usize;
+        let y1 = (y + radius + 1).min(height) as usize;
+        let area = ((x1 - x0) * (y1 - y0)) as i64;
+        let sum = integral[y1 * stride + x1] as i64
+            - integral[y0 * stride + x1] as i64
+            - integral[y1 * stride + x0] as i64
+            + integral[y0 * stride + x0] as i64;
+        let mean = (sum / area) as i32;
+        let value = gray.get_pixel(x, y).0[0] as i32;
+        image::Luma([if value < mean - ADAPTIVE_OFFSET {
+            0
+        } else {
+            255
+        }])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Luma;
+
+    /// Fondo blanco con "caracteres" negros de `char_height` píxeles en varias filas.
+    fn text_image(char_height: u32) -> GrayImage {
+        let (width, height) = (400, char_height * 8);
+        GrayImage::from_fn(width, height, |x, y| {
+            let in_row = (y % (char_height * 2)) < char_height && y >= char_height;
+            let in_char = x >= 10 && x < width - 10 && (x % 14) < 8;
+            Luma([if in_row && in_char { 0 } else { 255 }])
+        })
+    }
+
+    #[test]
+    fn otsu_separates_two_levels() {
+        let gray = GrayImage::from_fn(100, 100, |x, _| Luma([if x < 30 { 40 } else { 210 }]));
+        let level = otsu_level(&gray);
+        assert!((40..210).contains(&level), "umbral {}", level);
+        let binary = binarize(gray, level);
+        assert_eq!(binary.get_pixel(0, 0).0[0], 0);
+        assert_eq!(binary.get_pixel(99, 0).0[0], 255);
+    }
+
+    #[test]
+    fn detects_dark_background() {
+        let light = text_image(10);
+        let mut dark = light.clone();
+        image::imageops::invert(&mut dark);
+        assert!(!is_dark_background(&light));
+        assert!(is_dark_background(&dark));
+    }
+
+    #[test]
+    fn straight_text_has_no_skew() {
+        assert!(estimate_skew(&text_image(10)).abs() < MIN_SKEW_DEGREES);
+    }
+
+    #[test]
+    fn deskew_undoes_rotation() {
+        let gray = DynamicImage::ImageLuma8(text_image(10));
+        let (skewed, _) = rotate(&gray.to_rgba8(), 4.0);
+        let angle = estimate_skew(&DynamicImage::ImageRgba8(skewed).to_luma8());
+        assert!((angle + 4.0).abs() < 0.5, "ángulo {}", angle);
+    }
+
+    #[test]
+    fn other_engines_are_only_rotated() {
+        let image = DynamicImage::ImageRgba8(DynamicImage::ImageLuma8(text_image(10)).to_rgba8());
+        let settings = Preprocess {
+            rotation: 90,
+            deskew: false,
+            ..Preprocess::default()
+        };
+        let (oriented, _) = settings.for_engine(&image, false);
+        assert_eq!(oriented.width(), image.height());
+        assert_eq!(oriented.color(), image.color());
+
+        let (filtered, _) = settings.for_engine(&image, true);
+        assert_eq!(filtered.color(), image::ColorType::L8);
+    }
+
+    #[test]
+    fn auto_scale_targets_text_height() {
+        let settings = Preprocess::default();
+        let scale = settings.auto_scale_factor(&text_image(10));
+        assert!(
+            (scale - TARGET_TEXT_HEIGHT / 10.0).abs() < 0.01,
+            "escala {}",
+            scale
+        );
+
+        // Sin texto se usa la escala manual.
+        let blank = GrayImage::from_pixel(50, 50, Luma([255]));
+        assert_eq!(settings.auto_scale_factor(&blank), settings.scale);
+    }
+
+    #[test]
+    fn transform_maps_back_to_the_crop() {
+        let image = DynamicImage::ImageLuma8(text_image(10));
+        let settings = Preprocess {
+            rotation: 90,
+            deskew: false,
+            auto_scale: false,
+            scale: 2.0,
+            ..Preprocess::default()
+        };
+        let (processed, transform) = settings.apply(&image);
+        assert_eq!(processed.width(), image.height() * 2);
+        // La esquina superior derecha del resultado es la superior izquierda del original.
+        let corner = transform.source_point(egui::pos2(processed.width() as f32, 0.0));
+        assert!(corner.distance(egui::Pos2::ZERO) < 0.01, "{:?}", corner);
+    }
+}
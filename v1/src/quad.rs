@@ -0,0 +1,125 @@
+use eframe::egui::{self, Pos2, Vec2};
+
+/// Cuadrilátero en el orden de PaddleOCR: arriba-izquierda, arriba-derecha, abajo-derecha y
+/// abajo-izquierda.
+pub type Quad = [Pos2; 4];
+
+/// Coseno del ángulo máximo (10°) entre dos palabras de la misma línea.
+#[cfg(feature = "paddleocr")]
+const MIN_ALIGNMENT: f32 = 0.985;
+/// Desplazamiento máximo respecto a la línea base, en alturas de palabra.
+#[cfg(feature = "paddleocr")]
+const BASELINE_TOLERANCE: f32 = 0.5;
+/// Hueco máximo entre palabras de la misma línea, en alturas de palabra.
+#[cfg(feature = "paddleocr")]
+const LINE_GAP: f32 = 1.5;
+
+pub fn from_rect(rect: egui::Rect) -> Quad {
+    [
+        rect.left_top(),
+        rect.right_top(),
+        rect.right_bottom(),
+        rect.left_bottom(),
+    ]
+}
+
+/// Dirección unitaria del texto, media de los bordes superior e inferior.
+pub fn direction(quad: &Quad) -> Vec2 {
+    let d = (quad[1] - quad[0]) + (quad[2] - quad[3]);
+    if d.length() < f32::EPSILON {
+        Vec2::X
+    } else {
+        d.normalized()
+    }
+}
+
+/// Perpendicular a `direction`, apuntando hacia abajo del texto.
+fn normal(direction: Vec2) -> Vec2 {
+    egui::vec2(-direction.y, direction.x)
+}
+
+/// Caja orientada según `direction` que contiene todos los puntos.
+pub fn oriented_bounds(points: impl Iterator<Item = Pos2>, direction: Vec2) -> Quad {
+    let normal = normal(direction);
+    let (mut u0, mut u1, mut v0, mut v1) = (
+        f32::INFINITY,
+        f32::NEG_INFINITY,
+        f32::INFINITY,
+        f32::NEG_INFINITY,
+    );
+    for p in points {
+        let (u, v) = (p.to_vec2().dot(direction), p.to_vec2().dot(normal));
+        (u0, u1, v0, v1) = (u0.min(u), u1.max(u), v0.min(v), v1.max(v));
+    }
+    let corner = |u: f32, v: f32| (direction * u + normal * v).to_pos2();
+    [
+        corner(u0, v0),
+        corner(u1, v0),
+        corner(u1, v1),
+        corner(u0, v1),
+    ]
+}
+
+/// Prueba de pertenencia para cuadriláteros convexos en cualquier sentido de giro.
+pub fn contains(quad: &Quad, p: Pos2) -> bool {
+    let sides: Vec<f32> = (0..4)
+        .map(|i| {
+            let (a, b) = (quad[i], quad[(i + 1) % 4]);
+            (b - a).x * (p - a).y - (b - a).y * (p - a).x
+        })
+        .collect();
+    sides.iter().all(|&s| s >= 0.0) || sides.iter().all(|&s| s <= 0.0)
+}
+
+#[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+pub fn height(quad: &Quad) -> f32 {
+    ((quad[3] - quad[0]).length() + (quad[2] - quad[1]).length()) / 2.0
+}
+
+#[cfg(any(feature = "tesseract", feature = "paddleocr"))]
+pub fn width(quad: &Quad) -> f32 {
+    ((quad[1] - quad[0]).length() + (quad[2] - quad[3]).length()) / 2.0
+}
+
+#[cfg(feature = "paddleocr")]
+fn center(quad: &Quad) -> Pos2 {
+    (quad.iter().fold(Vec2::ZERO, |acc, p| acc + p.to_vec2()) / 4.0).to_pos2()
+}
+
+/// Posición del centro a lo largo de `direction`, para ordenar las palabras de una línea.
+#[cfg(feature = "paddleocr")]
+pub fn position_along(quad: &Quad, direction: Vec2) -> f32 {
+    center(quad).to_vec2().dot(direction)
+}
+
+#[cfg(feature = "paddleocr")]
+fn extent(quad: &Quad, direction: Vec2) -> (f32, f32) {
+    quad.iter()
+        .map(|p| p.to_vec2().dot(direction))
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), u| {
+            (lo.min(u), hi.max(u))
+        })
+}
+
+/// Indica si `quad` continúa la línea que empieza en `line[0]`: misma orientación, misma línea
+/// base y sin un hueco demasiado ancho respecto a alguna de sus palabras.
+#[cfg(feature = "paddleocr")]
+pub fn continues_line(line: &[Quad], quad: &Quad) -> bool {
+    let Some(reference) = line.first() else {
+        return false;
+    };
+    let dir = direction(reference);
+    if dir.dot(direction(quad)) < MIN_ALIGNMENT {
+        return false;
+    }
+    let h = height(reference).max(height(quad)).max(1.0);
+    let offset = (center(quad) - center(reference)).dot(normal(dir));
+    if offset.abs() > h * BASELINE_TOLERANCE {
+        return false;
+    }
+    let (start, end) = extent(quad, dir);
+    line.iter().any(|q| {
+        let (s, e) = extent(q, dir);
+        (start - e).max(s - end) < h * LINE_GAP
+    })
+}
@@ -0,0 +1,276 @@
+use crate::{OcrLine, OcrWord};
+
+/// Hueco mínimo entre columnas de la tabla, en alturas de palabra.
+const CELL_GAP: f32 = 1.0;
+
+/// Tabla inferida de la posición de las palabras: filas por solapamiento vertical y
+/// columnas por los huecos horizontales que comparten las filas alineadas.
+#[derive(Debug, Clone)]
+pub struct Table {
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Devuelve `None` si las palabras no forman al menos dos filas y dos columnas.
+    pub fn detect(lines: &[OcrLine]) -> Option<Table> {
+        let mut words: Vec<&OcrWord> = lines.iter().flat_map(|l| &l.words).collect();
+        if words.len() < 4 {
+            return None;
+        }
+
+        let mut heights: Vec<f32> = words.iter().map(|w| w.bbox.height()).collect();
+        heights.sort_by(f32::total_cmp);
+        let min_gap = heights[heights.len() / 2] * CELL_GAP;
+
+        words.sort_by(|a, b| a.bbox.center().y.total_cmp(&b.bbox.center().y));
+        let mut rows: Vec<(f32, f32, Vec<&OcrWord>)> = Vec::new();
+        for word in words {
+            let center = word.bbox.center().y;
+            match rows.last_mut() {
+                Some((top, bottom, row)) if center >= *top && center <= *bottom => {
+                    *top = top.min(word.bbox.min.y);
+                    *bottom = bottom.max(word.bbox.max.y);
+                    row.push(word);
+                }
+                _ => rows.push((word.bbox.min.y, word.bbox.max.y, vec![word])),
+            }
+        }
+
+        let rows_words: Vec<&[&OcrWord]> = rows.iter().map(|(_, _, row)| row.as_slice()).collect();
+        let aligned = rows_words
+            .iter()
+            .enumerate()
+            .filter(|&(i, row)| !crosses_columns(row, i, &rows_words, min_gap))
+            .flat_map(|(_, row)| row.iter());
+        let columns = column_ranges(aligned, min_gap);
+        if rows.len() < 2 || columns.len() < 2 {
+            return None;
+        }
+
+        let rows = rows
+            .into_iter()
+            .map(|(_, _, mut row)| {
+                row.sort_by(|a, b| a.bbox.min.x.total_cmp(&b.bbox.min.x));
+                let mut cells = vec![String::new(); columns.len()];
+                for word in row {
+                    // Las palabras de las filas que cruzan columnas, como un título, van a la
+                    // columna más cercana.
+                    let x = word.bbox.center().x;
+                    let column = columns
+                        .iter()
+                        .map(|&(start, end)| (start - x).max(x - end).max(0.0))
+                        .enumerate()
+                        .min_by(|a, b| a.1.total_cmp(&b.1))
+                        .map_or(0, |(i, _)| i);
+                    let cell = &mut cells[column];
+                    if !cell.is_empty() {
+                        cell.push(' ');
+                    }
+                    cell.push_str(word.text.trim());
+                }
+                cells
+            })
+            .collect();
+
+        Some(Table { rows })
+    }
+
+    pub fn to_csv(&self) -> String {
+        self.join_rows(",", |cell| {
+            if cell.contains([',', '"', '\n']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.to_string()
+            }
+        })
+    }
+
+    pub fn to_tsv(&self) -> String {
+        self.join_rows("\t", |cell| cell.replace(['\t', '\n'], " "))
+    }
+
+    /// La primera fila se usa como cabecera.
+    pub fn to_markdown(&self) -> String {
+        let columns = self.rows.first().map_or(0, Vec::len);
+        let mut lines: Vec<String> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let cells: Vec<String> = row.iter().map(|c| c.replace('|', "\\|")).collect();
+                format!("| {} |", cells.join(" | "))
+            })
+            .collect();
+        lines.insert(1, format!("|{}", " --- |".repeat(columns)));
+        lines.join("\n")
+    }
+
+    /// La primera fila se usa como cabecera, igual que en Markdown.
+    pub fn to_html(&self) -> String {
+        let escape = |cell: &str| {
+            cell.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+        };
+        let mut html = String::from("<table>\n");
+        for (i, row) in self.rows.iter().enumerate() {
+            let tag = if i == 0 { "th" } else { "td" };
+            html.push_str("<tr>");
+            for cell in row {
+                html.push_str(&format!("<{0}>{1}</{0}>", tag, escape(cell)));
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</table>");
+        html
+    }
+
+    fn join_rows(&self, separator: &str, escape: impl Fn(&str) -> String) -> String {
+        self.rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| escape(cell))
+                    .collect::<Vec<_>>()
+                    .join(separator)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Si algún tramo de la fila `index` (palabras a menos de `min_gap`) ocupa más de una de las
+/// columnas que forman las demás filas de varias celdas, como un título o una celda partida.
+fn crosses_columns(row: &[&OcrWord], index: usize, rows: &[&[&OcrWord]], min_gap: f32) -> bool {
+    let others = rows
+        .iter()
+        .enumerate()
+        .filter(|&(i, other)| i != index && column_ranges(other.iter(), min_gap).len() > 1)
+        .flat_map(|(_, other)| other.iter());
+    let columns = column_ranges(others, min_gap);
+    column_ranges(row.iter(), min_gap)
+        .iter()
+        .any(|&(start, end)| {
+            columns
+                .iter()
+                .filter(|&&(column_start, column_end)| start <= column_end && end >= column_start)
+                .count()
+                > 1
+        })
+}
+
+/// Intervalos de X ocupados por palabras, fusionando los que están a menos de `min_gap`.
+fn column_ranges<'a>(
+    words: impl Iterator<Item = &'a &'a OcrWord>,
+    min_gap: f32,
+) -> Vec<(f32, f32)> {
+    let mut ranges: Vec<(f32, f32)> = words.map(|w| (w.bbox.min.x, w.bbox.max.x)).collect();
+    ranges.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut merged: Vec<(f32, f32)> = Vec::new();
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start - last.1 < min_gap => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_grid() {
+        let lines = [
+            OcrLine::test(
+                vec![
+                    OcrWord::test("Name", 0.0, 0.0, 40.0, 10.0),
+                    OcrWord::test("Qty", 100.0, 0.0, 40.0, 10.0),
+                ],
+                0,
+            ),
+            OcrLine::test(
+                vec![
+                    OcrWord::test("a,b", 0.0, 20.0, 40.0, 10.0),
+                    OcrWord::test("2", 100.0, 21.0, 40.0, 10.0),
+                ],
+                0,
+            ),
+            OcrLine::test(
+                vec![
+                    OcrWord::test("c|d", 0.0, 40.0, 40.0, 10.0),
+                    OcrWord::test("<3>", 100.0, 39.0, 40.0, 10.0),
+                ],
+                0,
+            ),
+        ];
+        let table = Table::detect(&lines).expect("tabla de 3x2");
+        assert_eq!(table.to_csv(), "Name,Qty\n\"a,b\",2\nc|d,<3>");
+        assert_eq!(table.to_tsv(), "Name\tQty\na,b\t2\nc|d\t<3>");
+        assert_eq!(
+            table.to_markdown(),
+            "| Name | Qty |\n| --- | --- |\n| a,b | 2 |\n| c\\|d | <3> |"
+        );
+        assert!(table.to_html().contains("<td>&lt;3&gt;</td>"));
+    }
+
+    #[test]
+    fn words_in_the_same_cell_are_joined() {
+        let lines = [
+            OcrLine::test(
+                vec![
+                    OcrWord::test("New", 0.0, 0.0, 40.0, 10.0),
+                    OcrWord::test("York", 45.0, 0.0, 40.0, 10.0),
+                    OcrWord::test("8", 150.0, 0.0, 40.0, 10.0),
+                ],
+                0,
+            ),
+            OcrLine::test(
+                vec![
+                    OcrWord::test("Paris", 0.0, 20.0, 40.0, 10.0),
+                    OcrWord::test("2", 150.0, 20.0, 40.0, 10.0),
+                ],
+                0,
+            ),
+        ];
+        let table = Table::detect(&lines).expect("tabla de 2x2");
+        assert_eq!(table.to_csv(), "New York,8\nParis,2");
+    }
+
+    #[test]
+    fn title_spanning_the_columns_is_ignored() {
+        let lines = [
+            OcrLine::test(vec![OcrWord::test("Inventario", 0.0, 0.0, 130.0, 10.0)], 0),
+            OcrLine::test(
+                vec![
+                    OcrWord::test("Name", 0.0, 20.0, 40.0, 10.0),
+                    OcrWord::test("Qty", 120.0, 20.0, 40.0, 10.0),
+                ],
+                0,
+            ),
+            OcrLine::test(
+                vec![
+                    OcrWord::test("Pens", 0.0, 40.0, 40.0, 10.0),
+                    OcrWord::test("12", 120.0, 40.0, 40.0, 10.0),
+                ],
+                0,
+            ),
+        ];
+        let table = Table::detect(&lines).expect("tabla con título");
+        assert_eq!(table.to_csv(), "Inventario,\nName,Qty\nPens,12");
+    }
+
+    #[test]
+    fn rejects_single_column() {
+        let lines: Vec<OcrLine> = (0..4)
+            .map(|i| {
+                OcrLine::test(
+                    vec![OcrWord::test("texto", 0.0, i as f32 * 20.0, 40.0, 10.0)],
+                    0,
+                )
+            })
+            .collect();
+        assert!(Table::detect(&lines).is_none());
+    }
+}
@@ -0,0 +1,241 @@
+use image::GrayImage;
+use rusty_tesseract::{Args, Data};
+use std::collections::HashMap;
+use std::error::Error;
+use std::ffi::{CStr, CString, c_char};
+use std::ptr;
+use tesseract_sys::{
+    TessBaseAPI, TessBaseAPICreate, TessBaseAPIDelete, TessBaseAPIEnd, TessBaseAPIGetTsvText,
+    TessBaseAPIInit4, TessBaseAPIRecognize, TessBaseAPISetImage, TessBaseAPISetPageSegMode,
+    TessBaseAPISetSourceResolution, TessBaseAPISetVariable, TessDeleteText, TessOcrEngineMode,
+    TessOcrEngineMode_OEM_DEFAULT, TessOcrEngineMode_OEM_LSTM_ONLY,
+    TessOcrEngineMode_OEM_TESSERACT_LSTM_COMBINED, TessOcrEngineMode_OEM_TESSERACT_ONLY,
+    TessPageSegMode, TessPageSegMode_PSM_AUTO, TessPageSegMode_PSM_AUTO_ONLY,
+    TessPageSegMode_PSM_AUTO_OSD, TessPageSegMode_PSM_CIRCLE_WORD, TessPageSegMode_PSM_OSD_ONLY,
+    TessPageSegMode_PSM_RAW_LINE, TessPageSegMode_PSM_SINGLE_BLOCK,
+    TessPageSegMode_PSM_SINGLE_BLOCK_VERT_TEXT, TessPageSegMode_PSM_SINGLE_CHAR,
+    TessPageSegMode_PSM_SINGLE_COLUMN, TessPageSegMode_PSM_SINGLE_LINE,
+    TessPageSegMode_PSM_SINGLE_WORD, TessPageSegMode_PSM_SPARSE_TEXT,
+    TessPageSegMode_PSM_SPARSE_TEXT_OSD,
+};
+
+type TessResult<T> = Result<T, Box<dyn Error>>;
+
+/// Variables que Tesseract solo lee al inicializarse (`Init`); `SetVariable` no tiene efecto con
+/// ellas, así que se pasan al crear la instancia y forman parte de su clave.
+const INIT_VARIABLES: &[&str] = &["user_patterns_file", "user_words_file"];
+
+/// Idioma, OEM y variables de inicialización (ordenadas) de una instancia.
+type InstanceKey = (String, i32, Vec<(String, String)>);
+
+/// `TessBaseAPI` de la API en C. El envoltorio del crate `tesseract` pierde la instancia si
+/// `SetVariable` falla y no permite pasar variables en `Init`, por eso se usa directamente.
+struct BaseApi(*mut TessBaseAPI);
+
+impl Drop for BaseApi {
+    fn drop(&mut self) {
+        unsafe {
+            TessBaseAPIEnd(self.0);
+            TessBaseAPIDelete(self.0);
+        }
+    }
+}
+
+/// Instancia de libtesseract ya inicializada para un idioma, OEM y variables de inicio concretos.
+pub struct TessInstance {
+    api: BaseApi,
+    variables: HashMap<String, String>,
+}
+
+impl TessInstance {
+    fn new(lang: &str, oem: i32, init_variables: &[(String, String)]) -> TessResult<Self> {
+        println!("Inicializando Tesseract ({}, oem {})...", lang, oem);
+        let language = CString::new(lang)?;
+        let names = init_variables
+            .iter()
+            .map(|(name, _)| CString::new(name.as_str()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let values = init_variables
+            .iter()
+            .map(|(_, value)| CString::new(value.as_str()))
+            .collect::<Result<Vec<_>, _>>()?;
+        // La API en C pide `char**` aunque no modifica las cadenas.
+        let mut name_ptrs: Vec<*mut c_char> = names.iter().map(|n| n.as_ptr().cast_mut()).collect();
+        let mut value_ptrs: Vec<*mut c_char> =
+            values.iter().map(|v| v.as_ptr().cast_mut()).collect();
+
+        let handle = unsafe { TessBaseAPICreate() };
+        if handle.is_null() {
+            return Err("No se pudo crear la instancia de Tesseract".into());
+        }
+        let api = BaseApi(handle);
+        let ret = unsafe {
+            TessBaseAPIInit4(
+                api.0,
+                ptr::null(),
+                language.as_ptr(),
+                engine_mode(oem),
+                ptr::null_mut(),
+                0,
+                name_ptrs.as_mut_ptr(),
+                value_ptrs.as_mut_ptr(),
+                name_ptrs.len(),
+                0,
+            )
+        };
+        if ret != 0 {
+            return Err(format!("No se pudo inicializar Tesseract con '{}'", lang).into());
+        }
+        Ok(TessInstance {
+            api,
+            variables: HashMap::new(),
+        })
+    }
+
+    pub fn set_variable(&mut self, name: &str, value: &str) -> TessResult<()> {
+        if self.variables.get(name).map(String::as_str) == Some(value) {
+            return Ok(());
+        }
+        let (c_name, c_value) = (CString::new(name)?, CString::new(value)?);
+        if unsafe { TessBaseAPISetVariable(self.api.0, c_name.as_ptr(), c_value.as_ptr()) } != 1 {
+            return Err(format!("Tesseract rechazó la variable '{}'", name).into());
+        }
+        self.variables.insert(name.to_string(), value.to_string());
+        Ok(())
+    }
+
+    fn recognize(&mut self, image: &GrayImage, args: &Args) -> TessResult<Vec<Data>> {
+        let (width, height) = (image.width() as i32, image.height() as i32);
+        let tsv = unsafe {
+            TessBaseAPISetPageSegMode(self.api.0, page_seg_mode(args.psm.unwrap_or(3)));
+            // Una imagen en escala de grises: 1 byte por píxel y `width` bytes por fila.
+            TessBaseAPISetImage(self.api.0, image.as_raw().as_ptr(), width, height, 1, width);
+            if let Some(dpi) = args.dpi {
+                TessBaseAPISetSourceResolution(self.api.0, dpi);
+            }
+            if TessBaseAPIRecognize(self.api.0, ptr::null_mut()) != 0 {
+                return Err("Tesseract no pudo reconocer la imagen".into());
+            }
+            let text = TessBaseAPIGetTsvText(self.api.0, 0);
+            if text.is_null() {
+                return Err("Tesseract no devolvió el TSV".into());
+            }
+            let tsv = CStr::from_ptr(text).to_string_lossy().into_owned();
+            TessDeleteText(text);
+            tsv
+        };
+
+        Ok(parse_tsv(&tsv))
+    }
+}
+
+/// Mantiene una instancia de libtesseract por idioma para no recargar los traineddata en cada recorte.
+#[derive(Default)]
+pub struct TesseractApi {
+    instances: HashMap<InstanceKey, TessInstance>,
+}
+
+impl TesseractApi {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn instance(&mut self, key: &InstanceKey) -> TessResult<&mut TessInstance> {
+        if !self.instances.contains_key(key) {
+            let (lang, oem, init_variables) = key;
+            self.instances
+                .insert(key.clone(), TessInstance::new(lang, *oem, init_variables)?);
+        }
+        Ok(self
+            .instances
+            .get_mut(key)
+            .expect("instancia recién creada"))
+    }
+
+    /// Reconoce `image` y devuelve las palabras (nivel 5 del TSV) con sus ids de bloque/párrafo/línea.
+    pub fn recognize(&mut self, image: &GrayImage, args: &Args) -> TessResult<Vec<Data>> {
+        let (mut init_variables, variables): (Vec<_>, Vec<_>) = args
+            .config_variables
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .partition(|(name, _)| INIT_VARIABLES.contains(&name.as_str()));
+        init_variables.sort();
+        let key = (args.lang.clone(), args.oem.unwrap_or(3), init_variables);
+
+        // Tesseract no permite restaurar una variable a su valor por defecto: si se quitó alguna, se reinicia.
+        if self.instances.get(&key).is_some_and(|i| {
+            i.variables
+                .keys()
+                .any(|k| !variables.iter().any(|(name, _)| name == k))
+        }) {
+            self.instances.remove(&key);
+        }
+
+        let instance = self.instance(&key)?;
+        for (name, value) in &variables {
+            if let Err(e) = instance.set_variable(name, value) {
+                eprintln!("[tesseract] No se pudo aplicar '{}': {}", name, e);
+            }
+        }
+
+        Ok(instance
+            .recognize(image, args)?
+            .into_iter()
+            .filter(|d| d.level == 5)
+            .collect())
+    }
+}
+
+fn parse_tsv(tsv: &str) -> Vec<Data> {
+    tsv.lines()
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split('\t').collect();
+            if columns.len() < 11 {
+                return None;
+            }
+            let int = |i: usize| columns[i].trim().parse::<i32>().ok();
+            Some(Data {
+                level: int(0)?,
+                page_num: int(1)?,
+                block_num: int(2)?,
+                par_num: int(3)?,
+                line_num: int(4)?,
+                word_num: int(5)?,
+                left: int(6)?,
+                top: int(7)?,
+                width: int(8)?,
+                height: int(9)?,
+                conf: columns[10].trim().parse().ok()?,
+                text: columns.get(11).map(|t| t.to_string()).unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+fn engine_mode(oem: i32) -> TessOcrEngineMode {
+    match oem {
+        0 => TessOcrEngineMode_OEM_TESSERACT_ONLY,
+        1 => TessOcrEngineMode_OEM_LSTM_ONLY,
+        2 => TessOcrEngineMode_OEM_TESSERACT_LSTM_COMBINED,
+        _ => TessOcrEngineMode_OEM_DEFAULT,
+    }
+}
+
+fn page_seg_mode(psm: i32) -> TessPageSegMode {
+    match psm {
+        0 => TessPageSegMode_PSM_OSD_ONLY,
+        1 => TessPageSegMode_PSM_AUTO_OSD,
+        2 => TessPageSegMode_PSM_AUTO_ONLY,
+        4 => TessPageSegMode_PSM_SINGLE_COLUMN,
+        5 => TessPageSegMode_PSM_SINGLE_BLOCK_VERT_TEXT,
+        6 => TessPageSegMode_PSM_SINGLE_BLOCK,
+        7 => TessPageSegMode_PSM_SINGLE_LINE,
+        8 => TessPageSegMode_PSM_SINGLE_WORD,
+        9 => TessPageSegMode_PSM_CIRCLE_WORD,
+        10 => TessPageSegMode_PSM_SINGLE_CHAR,
+        11 => TessPageSegMode_PSM_SPARSE_TEXT,
+        12 => TessPageSegMode_PSM_SPARSE_TEXT_OSD,
+        13 => TessPageSegMode_PSM_RAW_LINE,
+        _ => TessPageSegMode_PSM_AUTO,
+    }
+}
@@ -0,0 +1,66 @@
+use eframe::egui;
+use image::RgbaImage;
+
+struct Tile {
+    texture: egui::TextureHandle,
+    /// Región de la captura (en píxeles) que cubre esta textura.
+    rect: egui::Rect,
+}
+
+/// Captura repartida en varias texturas para no superar `max_texture_side`.
+pub struct TiledTexture {
+    tiles: Vec<Tile>,
+}
+
+impl TiledTexture {
+    pub fn new(ctx: &egui::Context, image: &RgbaImage) -> Self {
+        let side = ctx.input(|i| i.max_texture_side).max(1) as u32;
+        let mut tiles = Vec::new();
+
+        for y in (0..image.height()).step_by(side as usize) {
+            for x in (0..image.width()).step_by(side as usize) {
+                let width = side.min(image.width() - x);
+                let height = side.min(image.height() - y);
+                let tile = image::imageops::crop_imm(image, x, y, width, height).to_image();
+                let color_image = egui::ColorImage::from_rgba_unmultiplied(
+                    [width as usize, height as usize],
+                    &tile,
+                );
+                tiles.push(Tile {
+                    texture: ctx.load_texture(
+                        format!("screenshot-tile-{}-{}", x, y),
+                        color_image,
+                        egui::TextureOptions::LINEAR,
+                    ),
+                    rect: egui::Rect::from_min_size(
+                        egui::pos2(x as f32, y as f32),
+                        egui::vec2(width as f32, height as f32),
+                    ),
+                });
+            }
+        }
+
+        TiledTexture { tiles }
+    }
+
+    /// Dibuja la parte `visible` de la captura (en píxeles) sobre `target` (en puntos).
+    pub fn paint(&self, painter: &egui::Painter, visible: egui::Rect, target: egui::Rect) {
+        let scale = target.size() / visible.size();
+        for tile in &self.tiles {
+            let part = tile.rect.intersect(visible);
+            if !part.is_positive() {
+                continue;
+            }
+            let screen = egui::Rect::from_min_max(
+                target.min + (part.min - visible.min) * scale,
+                target.min + (part.max - visible.min) * scale,
+            );
+            let size = tile.rect.size();
+            let uv = egui::Rect::from_min_max(
+                ((part.min - tile.rect.min) / size).to_pos2(),
+                ((part.max - tile.rect.min) / size).to_pos2(),
+            );
+            painter.image(tile.texture.id(), screen, uv, egui::Color32::WHITE);
+        }
+    }
+}